@@ -0,0 +1,386 @@
+//!
+//! Call JS functions from Rust with serialized arguments
+//!
+
+use crate::de::from_value;
+use crate::errors::{Error as LibError, Result as LibResult};
+use crate::ser::to_value;
+use neon::event::Channel;
+use neon::prelude::*;
+use neon::result::ResultExt;
+use serde::de::DeserializeOwned;
+use serde::ser::{self, Impossible, Serialize};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// A rooted JS function called with `Args` serialized into its arguments,
+/// its return value is deserialized into `Ret`
+///
+/// `Args` is passed like the arguments of `call_with`, `(value,)` for a
+/// single argument. An `export!` param written as `JsCallback<Args, Ret>`
+/// is read from a JS function argument
+///
+/// ```rust,no_run
+/// # use neon_serde4 as neon_serde;
+/// use neon::prelude::*;
+/// use neon_serde::{export, JsCallback};
+///
+/// export! {
+///     /// `progress(done, total)` is called from the worker thread and
+///     /// `false` cancels the work
+///     #[blocking]
+///     fn process(items: Vec<String>, progress: JsCallback<(usize, usize), bool>) -> usize {
+///         for done in 0..items.len() {
+///             if !progress.call_blocking((done, items.len())).unwrap_or(false) {
+///                 return done;
+///             }
+///         }
+///         items.len()
+///     }
+/// }
+/// ```
+pub struct JsCallback<Args, Ret = ()> {
+    function: Arc<Root<JsFunction>>,
+    channel: Channel,
+    marker: PhantomData<fn(Args) -> Ret>,
+}
+
+impl<Args, Ret> Clone for JsCallback<Args, Ret> {
+    fn clone(&self) -> Self {
+        JsCallback {
+            function: Arc::clone(&self.function),
+            channel: self.channel.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<Args, Ret> JsCallback<Args, Ret> {
+    /// Roots `function`, the callback does not keep the event loop alive
+    pub fn new<'a, C: Context<'a>>(cx: &mut C, function: Handle<JsFunction>) -> Self {
+        let mut channel = cx.channel();
+        channel.unref(cx);
+        JsCallback {
+            function: Arc::new(function.root(cx)),
+            channel,
+            marker: PhantomData,
+        }
+    }
+
+    /// The rooted function
+    pub fn function<'a, C: Context<'a>>(&self, cx: &mut C) -> Handle<'a, JsFunction> {
+        self.function.to_inner(cx)
+    }
+}
+
+impl<Args: Serialize, Ret> JsCallback<Args, Ret> {
+    fn apply<'a, C: Context<'a>>(&self, cx: &mut C, args: &Args) -> LibResult<Handle<'a, JsValue>> {
+        let function = self.function(cx);
+        let this = cx.undefined();
+        apply(cx, function, this, args)
+    }
+}
+
+impl<Args, Ret> JsCallback<Args, Ret>
+where
+    Args: Serialize,
+    Ret: DeserializeOwned,
+{
+    /// Calls the function on the JS thread with `this` set to `undefined`
+    ///
+    /// # Errors
+    ///
+    /// * any error of `call_with`
+    pub fn call<'a, C: Context<'a>>(&self, cx: &mut C, args: &Args) -> LibResult<Ret> {
+        let value = self.apply(cx, args)?;
+        from_value(cx, value)
+    }
+}
+
+impl<Args, Ret> JsCallback<Args, Ret>
+where
+    Args: Serialize + Send + 'static,
+    Ret: DeserializeOwned + Send + 'static,
+{
+    /// Schedules a call on the JS thread from any thread without waiting,
+    /// the return value is ignored and an exception is uncaught
+    ///
+    /// # Errors
+    ///
+    /// * `Callback` if the call could not be scheduled
+    pub fn send(&self, args: Args) -> LibResult<()> {
+        let callback = self.clone();
+        self.channel
+            .try_send(move |mut cx| match callback.apply(&mut cx, &args) {
+                Ok(_) => Ok(()),
+                Err(err) => err.or_throw(&mut cx),
+            })
+            .map(drop)
+            .map_err(|err| LibError::Callback {
+                msg: err.to_string(),
+            })
+    }
+
+    /// Calls the function on the JS thread from another thread and waits
+    /// for the result, calling it from the JS thread deadlocks
+    ///
+    /// # Errors
+    ///
+    /// * `Callback` if the function threw, its arguments or return value
+    ///   could not be converted or it could not be called
+    pub fn call_blocking(&self, args: Args) -> LibResult<Ret> {
+        let callback = self.clone();
+        let result = self
+            .channel
+            .try_send(move |mut cx| Ok(callback.call_caught(&mut cx, &args)))
+            .map_err(|err| err.to_string())
+            .and_then(|handle| handle.join().map_err(|err| err.to_string()));
+        match result {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(msg)) | Err(msg) => Err(LibError::Callback { msg }),
+        }
+    }
+
+    /// Neither a thrown exception nor `Error::Js` can leave the JS thread,
+    /// errors are replaced by their message
+    fn call_caught(&self, cx: &mut Cx, args: &Args) -> Result<Ret, String> {
+        let result = cx.try_catch(|cx| match self.call(cx, args) {
+            Err(LibError::Js { throw }) => Err(throw),
+            result => Ok(result.map_err(|err| err.to_string())),
+        });
+        result.unwrap_or_else(|exception| Err(exception_message(cx, exception)))
+    }
+}
+
+/// The message of a caught exception, an exception thrown while reading it
+/// is swallowed
+pub(crate) fn exception_message<'a, C: Context<'a>>(
+    cx: &mut C,
+    exception: Handle<JsValue>,
+) -> String {
+    cx.try_catch(|cx| Ok(exception.to_string(cx)?.value(cx)))
+        .unwrap_or_else(|_| String::from("JS exception"))
+}
+
+/// Calls `function` with `args` serialized into its arguments and
+/// deserializes the returned value
+///
+/// `args` is `()` for no arguments, a tuple or tuple struct with one
+/// element per argument or a struct whose fields are passed in order
+///
+/// ```rust,no_run
+/// # use neon_serde4 as neon_serde;
+/// use neon::prelude::*;
+///
+/// fn sort_by_key(mut cx: FunctionContext) -> JsResult<JsValue> {
+///     let key = cx.argument::<JsFunction>(0)?;
+///     let this = cx.undefined();
+///     let mut words = vec!["b", "aa", "c"];
+///     words.sort_by_cached_key(|word| {
+///         neon_serde::call_with::<_, u32>(&mut cx, key, this, &(*word,)).unwrap_or(0)
+///     });
+///     neon_serde::to_value(&mut cx, &words).or_else(|err| cx.throw_error(err.to_string()))
+/// }
+/// ```
+///
+/// # Errors
+///
+/// * `Js` if the function threw
+/// * `Serialize` if `args` is not `()`, a tuple or a struct
+/// * any error of `to_value` or `from_value`
+pub fn call_with<'j, Args, Ret>(
+    cx: &mut impl Context<'j>,
+    function: Handle<'j, JsFunction>,
+    this: Handle<'j, impl Value>,
+    args: &Args,
+) -> LibResult<Ret>
+where
+    Args: Serialize + ?Sized,
+    Ret: DeserializeOwned,
+{
+    let value = apply(cx, function, this, args)?;
+    from_value(cx, value)
+}
+
+fn apply<'j, C, T, Args>(
+    cx: &mut C,
+    function: Handle<'j, JsFunction>,
+    this: Handle<'j, T>,
+    args: &Args,
+) -> LibResult<Handle<'j, JsValue>>
+where
+    C: Context<'j>,
+    T: Value,
+    Args: Serialize + ?Sized,
+{
+    let args = args.serialize(ArgumentsSerializer {
+        cx,
+        ph: PhantomData,
+    })?;
+    Ok(function.call(cx, this, args)?)
+}
+
+/// Serializes `()`, a tuple or a struct into the argument list of a call
+struct ArgumentsSerializer<'a, 'j, C> {
+    cx: &'a mut C,
+    ph: PhantomData<&'j ()>,
+}
+
+/// Collects the elements of a tuple or the fields of a struct as arguments
+#[doc(hidden)]
+pub struct Arguments<'a, 'j, C> {
+    cx: &'a mut C,
+    args: Vec<Handle<'j, JsValue>>,
+}
+
+impl<'j, C: Context<'j>> Arguments<'_, 'j, C> {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<()> {
+        let value = to_value(self.cx, value)?;
+        self.args.push(value);
+        Ok(())
+    }
+}
+
+fn not_a_tuple<T>() -> LibResult<T> {
+    Err(ser::Error::custom(
+        "call arguments must be `()`, a tuple or a struct",
+    ))
+}
+
+macro_rules! not_a_tuple {
+    ($($method:ident($($type:ty),*) -> $ok:ty,)*) => {
+        $(
+            fn $method(self, $(_: $type),*) -> LibResult<$ok> {
+                not_a_tuple()
+            }
+        )*
+    };
+}
+
+impl<'a, 'j, C: Context<'j>> ser::Serializer for ArgumentsSerializer<'a, 'j, C> {
+    type Ok = Vec<Handle<'j, JsValue>>;
+    type Error = LibError;
+
+    type SerializeSeq = Impossible<Self::Ok, LibError>;
+    type SerializeTuple = Arguments<'a, 'j, C>;
+    type SerializeTupleStruct = Arguments<'a, 'j, C>;
+    type SerializeTupleVariant = Impossible<Self::Ok, LibError>;
+    type SerializeMap = Impossible<Self::Ok, LibError>;
+    type SerializeStruct = Arguments<'a, 'j, C>;
+    type SerializeStructVariant = Impossible<Self::Ok, LibError>;
+
+    not_a_tuple! {
+        serialize_bool(bool) -> Self::Ok,
+        serialize_i8(i8) -> Self::Ok,
+        serialize_i16(i16) -> Self::Ok,
+        serialize_i32(i32) -> Self::Ok,
+        serialize_i64(i64) -> Self::Ok,
+        serialize_u8(u8) -> Self::Ok,
+        serialize_u16(u16) -> Self::Ok,
+        serialize_u32(u32) -> Self::Ok,
+        serialize_u64(u64) -> Self::Ok,
+        serialize_f32(f32) -> Self::Ok,
+        serialize_f64(f64) -> Self::Ok,
+        serialize_char(char) -> Self::Ok,
+        serialize_str(&str) -> Self::Ok,
+        serialize_bytes(&[u8]) -> Self::Ok,
+        serialize_none() -> Self::Ok,
+        serialize_unit_variant(&'static str, u32, &'static str) -> Self::Ok,
+        serialize_seq(Option<usize>) -> Self::SerializeSeq,
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant,
+        serialize_map(Option<usize>) -> Self::SerializeMap,
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant,
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> LibResult<Self::Ok> {
+        not_a_tuple()
+    }
+
+    fn serialize_unit(self) -> LibResult<Self::Ok> {
+        Ok(Vec::new())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> LibResult<Self::Ok> {
+        Ok(Vec::new())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> LibResult<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> LibResult<Self::Ok> {
+        not_a_tuple()
+    }
+
+    fn serialize_tuple(self, len: usize) -> LibResult<Self::SerializeTuple> {
+        Ok(Arguments {
+            cx: self.cx,
+            args: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> LibResult<Self::SerializeTupleStruct> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> LibResult<Self::SerializeStruct> {
+        self.serialize_tuple(len)
+    }
+}
+
+impl<'j, C: Context<'j>> ser::SerializeTuple for Arguments<'_, 'j, C> {
+    type Ok = Vec<Handle<'j, JsValue>>;
+    type Error = LibError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> LibResult<Self::Ok> {
+        Ok(self.args)
+    }
+}
+
+impl<'j, C: Context<'j>> ser::SerializeTupleStruct for Arguments<'_, 'j, C> {
+    type Ok = Vec<Handle<'j, JsValue>>;
+    type Error = LibError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> LibResult<Self::Ok> {
+        Ok(self.args)
+    }
+}
+
+impl<'j, C: Context<'j>> ser::SerializeStruct for Arguments<'_, 'j, C> {
+    type Ok = Vec<Handle<'j, JsValue>>;
+    type Error = LibError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> LibResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> LibResult<Self::Ok> {
+        Ok(self.args)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::date::{JsDate as JsDateConverter, DT};
+use crate::date::{JsDate as JsDateConverter, CHRONO_DATE_TIME_INTERNAL_NAME, DT};
 use chrono::{DateTime, Utc};
 use serde::de;
 use serde_with::DeserializeAs;
@@ -30,9 +30,9 @@ impl<'de> de::Deserialize<'de> for DT<DateTime<Utc>> {
     where
         D: de::Deserializer<'de>,
     {
-        // Instantiate our Visitor and ask the Deserializer to drive
-        // it over the input data, resulting in an instance of MyMap.
-        deserializer.deserialize_i64(DTVisitor)
+        // the name lets `from_value` and the TypeScript tracer tell a date
+        // from any other number
+        deserializer.deserialize_newtype_struct(CHRONO_DATE_TIME_INTERNAL_NAME, DTVisitor)
     }
 }
 
@@ -45,6 +45,13 @@ impl<'de> de::Visitor<'de> for DTVisitor {
         formatter.write_str("a JS Date object")
     }
 
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_i64(self)
+    }
+
     fn visit_i64<E>(self, millis: i64) -> Result<Self::Value, E>
     where
         E: de::Error,
@@ -54,10 +61,19 @@ impl<'de> de::Visitor<'de> for DTVisitor {
         Ok(DT(date_time))
     }
 
+    fn visit_u64<E>(self, millis: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let millis = i64::try_from(millis).map_err(|_| E::custom("invalid date time"))?;
+        self.visit_i64(millis)
+    }
+
     fn visit_f64<E>(self, millis: f64) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
+        #[allow(clippy::cast_possible_truncation)]
         let date_time = DateTime::from_timestamp_millis(millis.round() as i64)
             .ok_or_else(|| E::custom("invalid date time"))?;
         Ok(DT(date_time))
@@ -36,7 +36,7 @@ where
 
 pub(crate) const CHRONO_DATE_TIME_INTERNAL_NAME: &str = "neon_serde::chrono::DT\x08";
 
-impl<'a, Tz: TimeZone> ser::Serialize for DT<&'a DateTime<Tz>> {
+impl<Tz: TimeZone> ser::Serialize for DT<&DateTime<Tz>> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: ser::Serializer,
@@ -55,12 +55,12 @@ where
     pub(crate) fn new(cx: &'a mut C) -> Self {
         DateTimeSerializer {
             cx,
-            phantom: Default::default(),
+            phantom: PhantomData,
         }
     }
 }
 
-impl<'a, 'j, C> ser::Serializer for DateTimeSerializer<'a, 'j, C>
+impl<'j, C> ser::Serializer for DateTimeSerializer<'_, 'j, C>
 where
     C: Context<'j>,
 {
@@ -75,6 +75,7 @@ where
     type SerializeStruct = ser::Impossible<Self::Ok, Self::Error>;
     type SerializeStructVariant = ser::Impossible<Self::Ok, Self::Error>;
 
+    #[allow(clippy::cast_precision_loss)]
     fn serialize_i64(self, millis: i64) -> Result<Self::Ok, Self::Error> {
         self.cx
             .date(millis as f64)
@@ -12,6 +12,7 @@ use serde::{
     },
     forward_to_deserialize_any,
 };
+use std::fmt;
 
 /// Deserialize an instance of type `T` from a `Handle<JsValue>`
 ///
@@ -19,14 +20,13 @@ use serde::{
 ///
 /// Can fail for various reasons see `Error`
 ///
+#[allow(clippy::needless_maybe_sized)]
 pub fn from_value<'j, C, T>(cx: &mut C, value: Handle<'j, JsValue>) -> LibResult<T>
 where
     C: Context<'j>,
     T: DeserializeOwned + ?Sized,
 {
-    let mut deserializer: Deserializer<C> = Deserializer::new(cx, value);
-    let t = T::deserialize(&mut deserializer)?;
-    Ok(t)
+    from_value_with(cx, value, Coercion::Standard)
 }
 
 /// Deserialize an instance of type `T` from an `Option<Handle<JsValue>>`
@@ -35,32 +35,412 @@ where
 ///
 /// Can fail for various reasons see `Error`
 ///
+#[allow(clippy::needless_maybe_sized)]
 pub fn from_value_opt<'j, C, T>(cx: &mut C, value: Option<Handle<'j, JsValue>>) -> LibResult<T>
 where
     C: Context<'j>,
     T: DeserializeOwned + ?Sized,
+{
+    from_value_opt_with(cx, value, Coercion::Standard)
+}
+
+/// Deserialize an instance of type `T` from a `Handle<JsValue>`
+/// using the given `Coercion` rules
+///
+/// # Errors
+///
+/// Can fail for various reasons see `Error`
+///
+pub fn from_value_with<'j, C, T>(
+    cx: &mut C,
+    value: Handle<'j, JsValue>,
+    coercion: Coercion,
+) -> LibResult<T>
+where
+    C: Context<'j>,
+    T: DeserializeOwned,
+{
+    let mut deserializer: Deserializer<C> = Deserializer::new(cx, value, coercion);
+    let t = T::deserialize(&mut deserializer)?;
+    Ok(t)
+}
+
+/// Deserialize an instance of type `T` from an `Option<Handle<JsValue>>`
+/// using the given `Coercion` rules
+///
+/// # Errors
+///
+/// Can fail for various reasons see `Error`
+///
+pub fn from_value_opt_with<'j, C, T>(
+    cx: &mut C,
+    value: Option<Handle<'j, JsValue>>,
+    coercion: Coercion,
+) -> LibResult<T>
+where
+    C: Context<'j>,
+    T: DeserializeOwned,
 {
     let unwrapped = value.unwrap_or_else(|| JsUndefined::new(cx).upcast());
-    from_value(cx, unwrapped)
+    from_value_with(cx, unwrapped, coercion)
+}
+
+/// How loosely a JS value may match the Rust type it is deserialized into
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Coercion {
+    /// Values are handed to serde as they are, the target type's visitor
+    /// decides what it accepts
+    #[default]
+    Standard,
+
+    /// JS-like coercions for loosely typed input such as query strings and
+    /// form data: finite numeric strings fill numbers, integers are parsed
+    /// without going through an `f64`, `0`/`1`, `"0"`/`"1"` and
+    /// `"true"`/`"false"` fill bools, numbers and bools fill strings and a number written with a
+    /// single character fills a `char`
+    Lenient,
+
+    /// The JS type and numeric range are checked against the target type
+    /// before serde sees the value, integers must be finite, whole and
+    /// within `Number.MAX_SAFE_INTEGER`, a mismatch is `Error::UnableToCoerce`
+    Strict,
 }
 
+/// Largest integer a JS number holds exactly, `Number.MAX_SAFE_INTEGER`
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
 #[doc(hidden)]
 pub struct Deserializer<'a, 'j, C: Context<'j> + 'a> {
     cx: &'a mut C,
     input: Handle<'j, JsValue>,
+    coercion: Coercion,
 }
 
 #[doc(hidden)]
 impl<'a, 'j, C: Context<'j>> Deserializer<'a, 'j, C> {
-    fn new(cx: &'a mut C, input: Handle<'j, JsValue>) -> Self {
-        Deserializer { cx, input }
+    fn new(cx: &'a mut C, input: Handle<'j, JsValue>, coercion: Coercion) -> Self {
+        Deserializer {
+            cx,
+            input,
+            coercion,
+        }
+    }
+
+    /// The input string under `Coercion::Lenient`, to be parsed into the target
+    fn lenient_str(&mut self) -> Option<String> {
+        if self.coercion != Coercion::Lenient {
+            return None;
+        }
+        let val = self.input.downcast::<JsString, C>(self.cx).ok()?;
+        Some(val.value(self.cx))
+    }
+
+    /// Reads a number out of a numeric string under `Coercion::Lenient`
+    fn lenient_number(&mut self, to_type: &'static str) -> LibResult<Option<f64>> {
+        match self.lenient_str() {
+            Some(s) => parse_lenient_float(&s)
+                .map(Some)
+                .ok_or_else(|| LibError::UnableToCoerce {
+                    to_type,
+                    found: format!("{s:?}"),
+                }),
+            None => Ok(None),
+        }
+    }
+
+    /// Stringifies a number or bool the way JS does under `Coercion::Lenient`
+    fn lenient_string(&mut self) -> Option<String> {
+        if self.coercion != Coercion::Lenient {
+            return None;
+        }
+        if let Ok(val) = self.input.downcast::<JsNumber, C>(self.cx) {
+            Some(number_to_string(val.value(self.cx)))
+        } else if let Ok(val) = self.input.downcast::<JsBoolean, C>(self.cx) {
+            Some(val.value(self.cx).to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Fails under `Coercion::Strict` unless the input has one of the `expected` types
+    fn check_strict(&mut self, expected: &[JsType], to_type: &'static str) -> LibResult<()> {
+        if self.coercion == Coercion::Strict {
+            check_strict_type(self.js_type(), expected, to_type)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a number under `Coercion::Strict`, any other JS type is an error
+    fn strict_number(&mut self, to_type: &'static str) -> LibResult<f64> {
+        check_strict_type(self.js_type(), &[JsType::Number], to_type)?;
+        let val =
+            self.input
+                .downcast::<JsNumber, C>(self.cx)
+                .map_err(|_| LibError::UnableToCoerce {
+                    to_type,
+                    found: JsType::Number.to_string(),
+                })?;
+        Ok(val.value(self.cx))
+    }
+
+    /// The JS type of the input as the strict checks see it
+    fn js_type(&mut self) -> JsType {
+        if self.input.is_a::<JsNull, C>(self.cx) {
+            JsType::Null
+        } else if self.input.is_a::<JsUndefined, C>(self.cx) {
+            JsType::Undefined
+        } else if self.input.is_a::<JsBoolean, C>(self.cx) {
+            JsType::Boolean
+        } else if self.input.is_a::<JsNumber, C>(self.cx) {
+            JsType::Number
+        } else if self.input.is_a::<JsString, C>(self.cx) {
+            JsType::String
+        } else if self.input.is_a::<JsBuffer, C>(self.cx) {
+            JsType::Buffer
+        } else if self.input.is_a::<JsArray, C>(self.cx) {
+            JsType::Array
+        } else if self.input.is_a::<JsDate, C>(self.cx) {
+            JsType::Date
+        } else if self.input.is_a::<JsFunction, C>(self.cx) {
+            JsType::Function
+        } else if self.input.is_a::<JsObject, C>(self.cx) {
+            JsType::Object
+        } else {
+            JsType::Other
+        }
     }
 }
 
-#[doc(hidden)]
-impl<'x, 'd, 'a, 'j, C: Context<'j>> serde::de::Deserializer<'x>
-    for &'d mut Deserializer<'a, 'j, C>
+/// The JS types `Coercion::Strict` tells apart
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum JsType {
+    Null,
+    Undefined,
+    Boolean,
+    Number,
+    String,
+    Buffer,
+    Array,
+    Date,
+    Function,
+    /// Any other object
+    Object,
+    /// Symbols and bigints
+    Other,
+}
+
+impl fmt::Display for JsType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsType::Null => "null",
+            JsType::Undefined => "undefined",
+            JsType::Boolean => "boolean",
+            JsType::Number => "number",
+            JsType::String => "string",
+            JsType::Buffer => "Buffer",
+            JsType::Array => "array",
+            JsType::Date => "Date",
+            JsType::Function => "function",
+            JsType::Object => "object",
+            JsType::Other => "symbol or bigint",
+        }
+        .fmt(f)
+    }
+}
+
+/// Fails with `UnableToCoerce` unless `found` is one of the `expected` types
+fn check_strict_type(found: JsType, expected: &[JsType], to_type: &'static str) -> LibResult<()> {
+    if expected.contains(&found) {
+        Ok(())
+    } else {
+        Err(LibError::UnableToCoerce {
+            to_type,
+            found: found.to_string(),
+        })
+    }
+}
+
+/// Checks that `v` converts to the integer type `to_type` without loss, it
+/// must be finite, whole, within `Number.MAX_SAFE_INTEGER` and `min..=max`
+fn check_strict_integer(v: f64, to_type: &'static str, min: f64, max: f64) -> LibResult<f64> {
+    #[allow(clippy::float_cmp)]
+    let is_integer = v.is_finite() && v.trunc() == v;
+    if !is_integer || v.abs() > MAX_SAFE_INTEGER || v < min || v > max {
+        return Err(LibError::UnableToCoerce {
+            to_type,
+            found: number_to_string(v),
+        });
+    }
+    Ok(v)
+}
+
+/// Checks that a finite `v` is within the range of `f32`
+fn check_strict_f32(v: f64) -> LibResult<f32> {
+    if v.is_finite() && v.abs() > f64::from(f32::MAX) {
+        return Err(LibError::UnableToCoerce {
+            to_type: "f32",
+            found: number_to_string(v),
+        });
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(v as f32)
+}
+
+/// Hands a JS number to the visitor as the most precise serde primitive,
+/// whole numbers go to `visit_u64` or `visit_i64` when they fit and everything
+/// else, including `-0`, `NaN` and the infinities, stays an `f64`
+pub(crate) fn visit_number<'x, V>(v: f64, visitor: V) -> LibResult<V::Value>
+where
+    V: Visitor<'x>,
+{
+    // both bounds are powers of two so they are exact as an `f64`
+    const I64_MIN: f64 = -9_223_372_036_854_775_808.0;
+    const U64_END: f64 = 18_446_744_073_709_551_616.0;
+
+    #[allow(clippy::float_cmp)]
+    let is_integer = v.is_finite() && v.trunc() == v && !(v == 0.0 && v.is_sign_negative());
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    if is_integer && (0.0..U64_END).contains(&v) {
+        visitor.visit_u64(v as u64)
+    } else if is_integer && (I64_MIN..0.0).contains(&v) {
+        visitor.visit_i64(v as i64)
+    } else {
+        visitor.visit_f64(v)
+    }
+}
+
+/// Parses a numeric string for a float target, the non-finite spellings
+/// Rust accepts, such as `"inf"` or `"NaN"`, are rejected
+fn parse_lenient_float(s: &str) -> Option<f64> {
+    s.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// Parses a numeric string for an integer target, whole numbers are parsed
+/// exactly instead of going through an `f64`
+fn parse_lenient_integer<T>(s: &str) -> Option<T>
+where
+    T: TryFrom<i128> + TryFrom<u128>,
 {
+    // 2^127, exact as an `f64`
+    const I128_END: f64 = 170_141_183_460_469_231_731_687_303_715_884_105_728.0;
+
+    let s = s.trim();
+    if let Ok(v) = s.parse::<i128>() {
+        return T::try_from(v).ok();
+    }
+    if let Ok(v) = s.parse::<u128>() {
+        return T::try_from(v).ok();
+    }
+    // decimal and exponent notation such as `"2.0"` or `"1e3"`
+    let v = parse_lenient_float(s)?;
+    #[allow(clippy::float_cmp)]
+    if v.trunc() != v || v.abs() >= I128_END {
+        return None;
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    T::try_from(v as i128).ok()
+}
+
+/// Parses `"true"`, `"false"` or the form encodings `"1"` and `"0"` for a
+/// bool target
+fn parse_lenient_bool(s: &str) -> Option<bool> {
+    match s {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Formats a number like JS `String(v)`, with the shortest digits that read
+/// back as `v` and an exponent below `1e-6` and from `1e21` on
+pub(crate) fn number_to_string(v: f64) -> String {
+    if v.is_nan() {
+        return "NaN".to_owned();
+    }
+    if v == 0.0 {
+        return "0".to_owned();
+    }
+    if v.is_sign_negative() {
+        return format!("-{}", number_to_string(-v));
+    }
+    if v.is_infinite() {
+        return "Infinity".to_owned();
+    }
+
+    // `{:e}` writes the shortest digits as `d.ddde-x`
+    let formatted = format!("{v:e}");
+    let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let digits = mantissa.replace('.', "");
+    let k = i32::try_from(digits.len()).unwrap_or(i32::MAX);
+    // `v` is `digits * 10^(n - k)`
+    let n = exponent.parse::<i32>().unwrap_or(0) + 1;
+
+    #[allow(clippy::cast_sign_loss)]
+    if k <= n && n <= 21 {
+        format!("{digits}{}", "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        format!("{int}.{frac}")
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{digits}", "0".repeat((-n) as usize))
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let sign = if n > 0 { "+" } else { "-" };
+        let exponent = (n - 1).abs();
+        if rest.is_empty() {
+            format!("{first}e{sign}{exponent}")
+        } else {
+            format!("{first}.{rest}e{sign}{exponent}")
+        }
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $ty:ident: $visit:ident,)*) => {
+        $(
+            #[allow(
+                clippy::cast_possible_truncation,
+                clippy::cast_lossless,
+                clippy::cast_precision_loss,
+                clippy::cast_sign_loss
+            )]
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'x>,
+            {
+                if self.coercion == Coercion::Strict {
+                    let to_type = stringify!($ty);
+                    let v = self.strict_number(to_type)?;
+                    let v = check_strict_integer(v, to_type, $ty::MIN as f64, $ty::MAX as f64)?;
+                    return visitor.$visit(v as $ty);
+                }
+                if let Some(s) = self.lenient_str() {
+                    return match parse_lenient_integer::<$ty>(&s) {
+                        Some(v) => visitor.$visit(v),
+                        None => Err(LibError::UnableToCoerce {
+                            to_type: stringify!($ty),
+                            found: format!("{s:?}"),
+                        }),
+                    };
+                }
+                let number = self
+                    .input
+                    .downcast::<JsNumber, C>(self.cx)
+                    .ok()
+                    .map(|val| val.value(self.cx));
+                match number {
+                    // integers have no negative zero, read `-0` as `0`
+                    Some(v) if v == 0.0 => visitor.visit_u64(0),
+                    Some(v) => visit_number(v, visitor),
+                    None => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+#[doc(hidden)]
+impl<'x, 'j, C: Context<'j>> serde::de::Deserializer<'x> for &mut Deserializer<'_, 'j, C> {
     type Error = LibError;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -76,30 +456,16 @@ impl<'x, 'd, 'a, 'j, C: Context<'j>> serde::de::Deserializer<'x>
         } else if let Ok(val) = self.input.downcast::<JsString, C>(self.cx) {
             visitor.visit_string(val.value(self.cx))
         } else if let Ok(val) = self.input.downcast::<JsNumber, C>(self.cx) {
-            let v = val.value(self.cx);
-            #[allow(clippy::float_cmp)]
-            if v.trunc() == v {
-                #[allow(clippy::cast_possible_truncation)]
-                visitor.visit_i64(v as i64)
-            } else {
-                visitor.visit_f64(v)
-            }
+            visit_number(val.value(self.cx), visitor)
         } else if let Ok(_val) = self.input.downcast::<JsBuffer, C>(self.cx) {
             self.deserialize_bytes(visitor)
         } else if let Ok(val) = self.input.downcast::<JsArray, C>(self.cx) {
-            let mut deserializer = JsArrayAccess::new(self.cx, val);
+            let mut deserializer = JsArrayAccess::new(self.cx, val, self.coercion);
             visitor.visit_seq(&mut deserializer)
         } else if let Ok(val) = self.input.downcast::<JsDate, C>(self.cx) {
-            let v = val.value(self.cx);
-            #[allow(clippy::float_cmp)]
-            if v.trunc() == v {
-                #[allow(clippy::cast_possible_truncation)]
-                visitor.visit_i64(v as i64)
-            } else {
-                visitor.visit_f64(v)
-            }
+            visit_number(val.value(self.cx), visitor)
         } else if let Ok(val) = self.input.downcast::<JsObject, C>(self.cx) {
-            let mut deserializer = JsObjectAccess::new(self.cx, val)?;
+            let mut deserializer = JsObjectAccess::new(self.cx, val, self.coercion)?;
             visitor.visit_map(&mut deserializer)
         } else {
             Err(LibError::NotImplemented {
@@ -108,6 +474,181 @@ impl<'x, 'd, 'a, 'j, C: Context<'j>> serde::de::Deserializer<'x>
         }
     }
 
+    deserialize_integer! {
+        deserialize_i8 => i8: visit_i8,
+        deserialize_i16 => i16: visit_i16,
+        deserialize_i32 => i32: visit_i32,
+        deserialize_i64 => i64: visit_i64,
+        deserialize_i128 => i128: visit_i128,
+        deserialize_u8 => u8: visit_u8,
+        deserialize_u16 => u16: visit_u16,
+        deserialize_u32 => u32: visit_u32,
+        deserialize_u64 => u64: visit_u64,
+        deserialize_u128 => u128: visit_u128,
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        if self.coercion == Coercion::Strict {
+            let v = self.strict_number("f32")?;
+            return visitor.visit_f32(check_strict_f32(v)?);
+        }
+        match self.lenient_number("f32")? {
+            Some(v) => visit_number(v, visitor),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        if self.coercion == Coercion::Strict {
+            let v = self.strict_number("f64")?;
+            return visitor.visit_f64(v);
+        }
+        match self.lenient_number("f64")? {
+            Some(v) => visit_number(v, visitor),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.check_strict(&[JsType::Boolean], "bool")?;
+        if self.coercion == Coercion::Lenient {
+            if let Ok(val) = self.input.downcast::<JsNumber, C>(self.cx) {
+                let v = val.value(self.cx);
+                #[allow(clippy::float_cmp)]
+                return match v {
+                    _ if v == 0.0 => visitor.visit_bool(false),
+                    _ if v == 1.0 => visitor.visit_bool(true),
+                    _ => Err(LibError::UnableToCoerce {
+                        to_type: "bool",
+                        found: number_to_string(v),
+                    }),
+                };
+            } else if let Some(s) = self.lenient_str() {
+                return match parse_lenient_bool(&s) {
+                    Some(v) => visitor.visit_bool(v),
+                    None => Err(LibError::UnableToCoerce {
+                        to_type: "bool",
+                        found: format!("{s:?}"),
+                    }),
+                };
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.check_strict(&[JsType::String], "char")?;
+        if self.input.is_a::<JsNumber, C>(self.cx) {
+            if let Some(s) = self.lenient_string() {
+                let mut chars = s.chars();
+                return match (chars.next(), chars.next()) {
+                    (Some(c), None) => visitor.visit_char(c),
+                    _ => Err(LibError::UnableToCoerce {
+                        to_type: "char",
+                        found: s,
+                    }),
+                };
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.check_strict(&[JsType::String], "string")?;
+        match self.lenient_string() {
+            Some(s) => visitor.visit_string(s),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.check_strict(&[JsType::Null, JsType::Undefined], "unit")?;
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.check_strict(&[JsType::Array], "seq")?;
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.check_strict(&[JsType::Object, JsType::Function], "map")?;
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        self.deserialize_map(visitor)
+    }
+
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'x>,
@@ -132,21 +673,29 @@ impl<'x, 'd, 'a, 'j, C: Context<'j>> serde::de::Deserializer<'x>
     {
         if let Ok(val) = self.input.downcast::<JsString, C>(self.cx) {
             let key_value = val.value(self.cx);
-            visitor.visit_enum(JsEnumAccess::new(self.cx, key_value, None))
+            visitor.visit_enum(JsEnumAccess::new(self.cx, key_value, None, self.coercion))
         } else if let Ok(val) = self.input.downcast::<JsObject, C>(self.cx) {
             let prop_names = val.get_own_property_names(self.cx)?;
             let len = prop_names.len(self.cx);
             if len != 1 {
                 return Err(LibError::InvalidKeyType {
-                    key: format!("object key with {} properties", len),
+                    key: format!("object key with {len} properties"),
                 });
             }
             let key = prop_names
                 .get::<JsValue, _, _>(self.cx, 0)?
-                .downcast_or_throw::<JsString, C>(self.cx)?;
+                .downcast::<JsString, C>(self.cx)
+                .map_err(|err| LibError::Deserialize {
+                    msg: err.to_string(),
+                })?;
             let enum_value = val.get(self.cx, key)?;
             let key_value = key.value(self.cx);
-            visitor.visit_enum(JsEnumAccess::new(self.cx, key_value, Some(enum_value)))
+            visitor.visit_enum(JsEnumAccess::new(
+                self.cx,
+                key_value,
+                Some(enum_value),
+                self.coercion,
+            ))
         } else {
             let m = self.input.to_string(self.cx)?.value(self.cx);
             Err(LibError::InvalidKeyType { key: m })
@@ -157,7 +706,12 @@ impl<'x, 'd, 'a, 'j, C: Context<'j>> serde::de::Deserializer<'x>
     where
         V: Visitor<'x>,
     {
-        let buff = self.input.downcast_or_throw::<JsBuffer, C>(self.cx)?;
+        let buff =
+            self.input
+                .downcast::<JsBuffer, C>(self.cx)
+                .map_err(|err| LibError::Deserialize {
+                    msg: err.to_string(),
+                })?;
         let copy = Vec::from(buff.as_slice(self.cx));
         visitor.visit_bytes(&copy)
     }
@@ -166,10 +720,12 @@ impl<'x, 'd, 'a, 'j, C: Context<'j>> serde::de::Deserializer<'x>
     where
         V: Visitor<'x>,
     {
-        let buff = self
-            .input
-            .downcast::<JsBuffer, C>(self.cx)
-            .or_throw(self.cx)?;
+        let buff =
+            self.input
+                .downcast::<JsBuffer, C>(self.cx)
+                .map_err(|err| LibError::Deserialize {
+                    msg: err.to_string(),
+                })?;
         let copy = Vec::from(buff.as_slice(self.cx));
         visitor.visit_byte_buf(copy)
     }
@@ -181,11 +737,29 @@ impl<'x, 'd, 'a, 'j, C: Context<'j>> serde::de::Deserializer<'x>
         visitor.visit_unit()
     }
 
+    fn deserialize_newtype_struct<V>(
+        self,
+        #[allow(unused)] name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        // a `date::JsDate` field reads the `Date` as its timestamp, also
+        // under `Coercion::Strict` where no other number target takes a `Date`
+        #[cfg(feature = "chrono")]
+        if name == crate::date::CHRONO_DATE_TIME_INTERNAL_NAME {
+            if let Ok(val) = self.input.downcast::<JsDate, C>(self.cx) {
+                return visit_number(val.value(self.cx), visitor);
+            }
+            return self.deserialize_i64(visitor);
+        }
+        self.deserialize_any(visitor)
+    }
+
     forward_to_deserialize_any! {
        <V: Visitor<'x>>
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        unit unit_struct seq tuple tuple_struct map struct identifier
-        newtype_struct
+        identifier
     }
 }
 
@@ -195,22 +769,24 @@ struct JsArrayAccess<'a, 'j, C: Context<'j> + 'a> {
     input: Handle<'j, JsArray>,
     idx: u32,
     len: u32,
+    coercion: Coercion,
 }
 
 #[doc(hidden)]
 impl<'a, 'j, C: Context<'j>> JsArrayAccess<'a, 'j, C> {
-    fn new(cx: &'a mut C, input: Handle<'j, JsArray>) -> Self {
+    fn new(cx: &'a mut C, input: Handle<'j, JsArray>, coercion: Coercion) -> Self {
         JsArrayAccess {
             len: input.len(cx),
             cx,
             input,
             idx: 0,
+            coercion,
         }
     }
 }
 
 #[doc(hidden)]
-impl<'x, 'a, 'j, C: Context<'j>> SeqAccess<'x> for JsArrayAccess<'a, 'j, C> {
+impl<'x, 'j, C: Context<'j>> SeqAccess<'x> for JsArrayAccess<'_, 'j, C> {
     type Error = LibError;
 
     fn next_element_seed<T>(&mut self, seed: T) -> LibResult<Option<T::Value>>
@@ -223,7 +799,7 @@ impl<'x, 'a, 'j, C: Context<'j>> SeqAccess<'x> for JsArrayAccess<'a, 'j, C> {
         let v = self.input.get(self.cx, self.idx)?;
         self.idx += 1;
 
-        let mut de = Deserializer::new(self.cx, v);
+        let mut de = Deserializer::new(self.cx, v, self.coercion);
         seed.deserialize(&mut de).map(Some)
     }
 }
@@ -235,11 +811,12 @@ struct JsObjectAccess<'a, 'j, C: Context<'j> + 'a> {
     prop_names: Handle<'j, JsArray>,
     idx: u32,
     len: u32,
+    coercion: Coercion,
 }
 
 #[doc(hidden)]
-impl<'x, 'a, 'j, C: Context<'j>> JsObjectAccess<'a, 'j, C> {
-    fn new(cx: &'a mut C, input: Handle<'j, JsObject>) -> LibResult<Self> {
+impl<'a, 'j, C: Context<'j>> JsObjectAccess<'a, 'j, C> {
+    fn new(cx: &'a mut C, input: Handle<'j, JsObject>, coercion: Coercion) -> LibResult<Self> {
         let prop_names = input.get_own_property_names(cx)?;
         let len = prop_names.len(cx);
 
@@ -249,12 +826,13 @@ impl<'x, 'a, 'j, C: Context<'j>> JsObjectAccess<'a, 'j, C> {
             prop_names,
             idx: 0,
             len,
+            coercion,
         })
     }
 }
 
 #[doc(hidden)]
-impl<'x, 'a, 'j, C: Context<'j>> MapAccess<'x> for JsObjectAccess<'a, 'j, C> {
+impl<'x, 'j, C: Context<'j>> MapAccess<'x> for JsObjectAccess<'_, 'j, C> {
     type Error = LibError;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
@@ -265,10 +843,10 @@ impl<'x, 'a, 'j, C: Context<'j>> MapAccess<'x> for JsObjectAccess<'a, 'j, C> {
             return Ok(None);
         }
 
-        let prop_name = self.prop_names.get(self.cx, self.idx)?;
+        let prop_name = self.prop_names.get::<JsString, _, _>(self.cx, self.idx)?;
 
-        let mut de = Deserializer::new(self.cx, prop_name);
-        seed.deserialize(&mut de).map(Some)
+        let de = MapKeyDeserializer::new(prop_name.value(self.cx));
+        seed.deserialize(de).map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
@@ -285,26 +863,140 @@ impl<'x, 'a, 'j, C: Context<'j>> MapAccess<'x> for JsObjectAccess<'a, 'j, C> {
         let value = self.input.get(self.cx, prop_name)?;
 
         self.idx += 1;
-        let mut de = Deserializer::new(self.cx, value);
+        let mut de = Deserializer::new(self.cx, value, self.coercion);
         let res = seed.deserialize(&mut de)?;
         Ok(res)
     }
 }
 
+/// Deserializes a property name, JS object keys are always strings so
+/// numbers, bools and chars are parsed back when the target type asks for them
+#[doc(hidden)]
+pub(crate) struct MapKeyDeserializer {
+    key: String,
+}
+
+#[doc(hidden)]
+impl MapKeyDeserializer {
+    pub(crate) fn new(key: String) -> Self {
+        MapKeyDeserializer { key }
+    }
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'x>,
+            {
+                match self.key.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    // let the visitor report the type mismatch
+                    Err(_) => visitor.visit_string(self.key),
+                }
+            }
+        )*
+    };
+}
+
+#[doc(hidden)]
+impl<'x> serde::de::Deserializer<'x> for MapKeyDeserializer {
+    type Error = LibError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        visitor.visit_string(self.key)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        match self.key.as_str() {
+            "true" => visitor.visit_bool(true),
+            "false" => visitor.visit_bool(false),
+            _ => visitor.visit_string(self.key),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'x>,
+    {
+        use serde::de::IntoDeserializer;
+        visitor.visit_enum(self.key.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+       <V: Visitor<'x>>
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
 #[doc(hidden)]
 struct JsEnumAccess<'a, 'j, C: Context<'j> + 'a> {
     cx: &'a mut C,
     variant: String,
     value: Option<Handle<'j, JsValue>>,
+    coercion: Coercion,
 }
 
 #[doc(hidden)]
 impl<'a, 'j, C: Context<'j>> JsEnumAccess<'a, 'j, C> {
-    fn new(cx: &'a mut C, key: String, value: Option<Handle<'j, JsValue>>) -> Self {
+    fn new(
+        cx: &'a mut C,
+        key: String,
+        value: Option<Handle<'j, JsValue>>,
+        coercion: Coercion,
+    ) -> Self {
         JsEnumAccess {
             cx,
             variant: key,
             value,
+            coercion,
         }
     }
 }
@@ -320,7 +1012,7 @@ impl<'x, 'a, 'j, C: Context<'j>> EnumAccess<'x> for JsEnumAccess<'a, 'j, C> {
     {
         use serde::de::IntoDeserializer;
         let variant = self.variant.into_deserializer();
-        let variant_access = JsVariantAccess::new(self.cx, self.value);
+        let variant_access = JsVariantAccess::new(self.cx, self.value, self.coercion);
         seed.deserialize(variant).map(|v| (v, variant_access))
     }
 }
@@ -329,23 +1021,28 @@ impl<'x, 'a, 'j, C: Context<'j>> EnumAccess<'x> for JsEnumAccess<'a, 'j, C> {
 struct JsVariantAccess<'a, 'j, C: Context<'j> + 'a> {
     cx: &'a mut C,
     value: Option<Handle<'j, JsValue>>,
+    coercion: Coercion,
 }
 
 #[doc(hidden)]
 impl<'a, 'j, C: Context<'j>> JsVariantAccess<'a, 'j, C> {
-    fn new(cx: &'a mut C, value: Option<Handle<'j, JsValue>>) -> Self {
-        JsVariantAccess { cx, value }
+    fn new(cx: &'a mut C, value: Option<Handle<'j, JsValue>>, coercion: Coercion) -> Self {
+        JsVariantAccess {
+            cx,
+            value,
+            coercion,
+        }
     }
 }
 
 #[doc(hidden)]
-impl<'x, 'a, 'j, C: Context<'j>> VariantAccess<'x> for JsVariantAccess<'a, 'j, C> {
+impl<'x, 'j, C: Context<'j>> VariantAccess<'x> for JsVariantAccess<'_, 'j, C> {
     type Error = LibError;
 
     fn unit_variant(self) -> Result<(), Self::Error> {
         match self.value {
             Some(val) => {
-                let mut deserializer = Deserializer::new(self.cx, val);
+                let mut deserializer = Deserializer::new(self.cx, val, self.coercion);
                 serde::de::Deserialize::deserialize(&mut deserializer)
             }
             None => Ok(()),
@@ -358,7 +1055,7 @@ impl<'x, 'a, 'j, C: Context<'j>> VariantAccess<'x> for JsVariantAccess<'a, 'j, C
     {
         match self.value {
             Some(val) => {
-                let mut deserializer = Deserializer::new(self.cx, val);
+                let mut deserializer = Deserializer::new(self.cx, val, self.coercion);
                 seed.deserialize(&mut deserializer)
             }
             None => Err(serde::de::Error::invalid_type(
@@ -375,7 +1072,7 @@ impl<'x, 'a, 'j, C: Context<'j>> VariantAccess<'x> for JsVariantAccess<'a, 'j, C
         match self.value {
             Some(handle) => {
                 if let Ok(val) = handle.downcast::<JsArray, C>(self.cx) {
-                    let mut deserializer = JsArrayAccess::new(self.cx, val);
+                    let mut deserializer = JsArrayAccess::new(self.cx, val, self.coercion);
                     visitor.visit_seq(&mut deserializer)
                 } else {
                     Err(serde::de::Error::invalid_type(
@@ -402,7 +1099,7 @@ impl<'x, 'a, 'j, C: Context<'j>> VariantAccess<'x> for JsVariantAccess<'a, 'j, C
         match self.value {
             Some(handle) => {
                 if let Ok(val) = handle.downcast::<JsObject, C>(self.cx) {
-                    let mut deserializer = JsObjectAccess::new(self.cx, val)?;
+                    let mut deserializer = JsObjectAccess::new(self.cx, val, self.coercion)?;
                     visitor.visit_map(&mut deserializer)
                 } else {
                     Err(serde::de::Error::invalid_type(
@@ -418,3 +1115,275 @@ impl<'x, 'a, 'j, C: Context<'j>> VariantAccess<'x> for JsVariantAccess<'a, 'j, C
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_strict_f32, check_strict_integer, check_strict_type, number_to_string,
+        parse_lenient_bool, parse_lenient_float, parse_lenient_integer, visit_number, JsType,
+        MapKeyDeserializer,
+    };
+    use crate::errors::Error as LibError;
+    use serde::de::{Deserialize, Visitor};
+    use std::fmt;
+
+    fn key(k: &str) -> MapKeyDeserializer {
+        MapKeyDeserializer::new(k.to_owned())
+    }
+
+    #[test]
+    fn test_map_keys_are_parsed() {
+        assert_eq!(u32::deserialize(key("42")).unwrap(), 42);
+        assert_eq!(i64::deserialize(key("-7")).unwrap(), -7);
+        assert!((f64::deserialize(key("1.5")).unwrap() - 1.5).abs() < f64::EPSILON);
+        assert!(bool::deserialize(key("true")).unwrap());
+        assert_eq!(char::deserialize(key("x")).unwrap(), 'x');
+        assert_eq!(String::deserialize(key("12")).unwrap(), "12");
+    }
+
+    #[test]
+    fn test_map_keys_reject_mismatched_types() {
+        assert!(u8::deserialize(key("256")).is_err());
+        assert!(u32::deserialize(key("abc")).is_err());
+        assert!(bool::deserialize(key("1")).is_err());
+        assert!(char::deserialize(key("ab")).is_err());
+    }
+
+    struct VisitedAs;
+
+    impl Visitor<'_> for VisitedAs {
+        type Value = &'static str;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a number")
+        }
+
+        fn visit_i64<E>(self, _v: i64) -> Result<Self::Value, E> {
+            Ok("i64")
+        }
+
+        fn visit_u64<E>(self, _v: u64) -> Result<Self::Value, E> {
+            Ok("u64")
+        }
+
+        fn visit_f64<E>(self, _v: f64) -> Result<Self::Value, E> {
+            Ok("f64")
+        }
+    }
+
+    #[test]
+    fn test_numbers_visit_the_closest_primitive() {
+        let visited = |v: f64| visit_number(v, VisitedAs).unwrap();
+        assert_eq!(visited(0.0), "u64");
+        assert_eq!(visited(9_223_372_036_854_775_808.0), "u64");
+        assert_eq!(visited(-1.0), "i64");
+        assert_eq!(visited(-0.0), "f64");
+        assert_eq!(visited(1.5), "f64");
+        assert_eq!(visited(1e300), "f64");
+        assert_eq!(visited(-1e300), "f64");
+        assert_eq!(visited(f64::NAN), "f64");
+        assert_eq!(visited(f64::INFINITY), "f64");
+    }
+
+    #[test]
+    fn test_lenient_numeric_strings() {
+        assert_eq!(parse_lenient_float(" 1.5 "), Some(1.5));
+        assert_eq!(parse_lenient_float("-2e3"), Some(-2000.0));
+        assert_eq!(parse_lenient_integer::<u8>("42"), Some(42));
+        assert_eq!(parse_lenient_integer::<i32>(" -7\n"), Some(-7));
+        assert_eq!(parse_lenient_integer::<u32>("1e3"), Some(1000));
+        assert_eq!(parse_lenient_integer::<i64>("2.0"), Some(2));
+        // above 2^53 whole numbers keep every digit
+        assert_eq!(
+            parse_lenient_integer::<i64>("9007199254740993"),
+            Some(9_007_199_254_740_993)
+        );
+        assert_eq!(
+            parse_lenient_integer::<u64>("18446744073709551615"),
+            Some(u64::MAX)
+        );
+        assert_eq!(
+            parse_lenient_integer::<i128>(&i128::MIN.to_string()),
+            Some(i128::MIN)
+        );
+        assert_eq!(
+            parse_lenient_integer::<u128>(&u128::MAX.to_string()),
+            Some(u128::MAX)
+        );
+    }
+
+    #[test]
+    fn test_lenient_numeric_strings_are_rejected() {
+        for s in [
+            "",
+            "abc",
+            "1.5.0",
+            "inf",
+            "-infinity",
+            "Infinity",
+            "NaN",
+            "1e400",
+        ] {
+            assert_eq!(parse_lenient_float(s), None, "{s:?}");
+            assert_eq!(parse_lenient_integer::<i64>(s), None, "{s:?}");
+        }
+        assert_eq!(parse_lenient_integer::<u8>("256"), None);
+        assert_eq!(parse_lenient_integer::<u32>("-1"), None);
+        assert_eq!(parse_lenient_integer::<i32>("1.5"), None);
+        assert_eq!(parse_lenient_integer::<u64>("18446744073709551616"), None);
+    }
+
+    #[test]
+    fn test_lenient_bools() {
+        assert_eq!(parse_lenient_bool("true"), Some(true));
+        assert_eq!(parse_lenient_bool("false"), Some(false));
+        assert_eq!(parse_lenient_bool("1"), Some(true));
+        assert_eq!(parse_lenient_bool("0"), Some(false));
+        assert_eq!(parse_lenient_bool("TRUE"), None);
+        assert_eq!(parse_lenient_bool("2"), None);
+        assert_eq!(parse_lenient_bool("1.0"), None);
+        assert_eq!(parse_lenient_bool(""), None);
+    }
+
+    #[test]
+    fn test_numbers_are_stringified_like_js() {
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (42.0, "42"),
+            (-7.0, "-7"),
+            (1.5, "1.5"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (123_456.789, "123456.789"),
+            (1e20, "100000000000000000000"),
+            (1e21, "1e+21"),
+            (1.5e300, "1.5e+300"),
+            (0.000_001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-2.5e-10, "-2.5e-10"),
+            (f64::NAN, "NaN"),
+            (f64::INFINITY, "Infinity"),
+            (f64::NEG_INFINITY, "-Infinity"),
+        ];
+        for (v, expected) in cases {
+            assert_eq!(number_to_string(v), expected, "{v:?}");
+        }
+    }
+
+    fn is_unable_to_coerce<T: fmt::Debug>(
+        result: &Result<T, LibError>,
+        to: &str,
+        from: &str,
+    ) -> bool {
+        matches!(
+            result,
+            Err(LibError::UnableToCoerce { to_type, found }) if *to_type == to && found == from
+        )
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_strict_integers() {
+        #[allow(clippy::cast_precision_loss)]
+        let check = |v: f64| check_strict_integer(v, "i64", i64::MIN as f64, i64::MAX as f64);
+        assert_eq!(check(0.0).unwrap(), 0.0);
+        assert_eq!(check(-42.0).unwrap(), -42.0);
+        assert_eq!(
+            check(9_007_199_254_740_991.0).unwrap(),
+            9_007_199_254_740_991.0
+        );
+        assert_eq!(
+            check(-9_007_199_254_740_991.0).unwrap(),
+            -9_007_199_254_740_991.0
+        );
+        for v in [
+            1.5,
+            -0.1,
+            f64::NAN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            9_007_199_254_740_992.0,
+            -9_007_199_254_740_992.0,
+            1e300,
+        ] {
+            assert!(
+                is_unable_to_coerce(&check(v), "i64", &number_to_string(v)),
+                "{v:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_strict_integers_are_range_checked() {
+        let u8 = |v: f64| check_strict_integer(v, "u8", 0.0, 255.0);
+        assert_eq!(u8(255.0).unwrap(), 255.0);
+        assert!(is_unable_to_coerce(&u8(300.0), "u8", "300"));
+        assert!(is_unable_to_coerce(&u8(-1.0), "u8", "-1"));
+
+        let u32 = |v: f64| check_strict_integer(v, "u32", 0.0, f64::from(u32::MAX));
+        assert_eq!(u32(4_294_967_295.0).unwrap(), 4_294_967_295.0);
+        assert!(is_unable_to_coerce(&u32(-1.0), "u32", "-1"));
+        assert!(is_unable_to_coerce(
+            &u32(4_294_967_296.0),
+            "u32",
+            "4294967296"
+        ));
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_strict_floats() {
+        assert!((check_strict_f32(1.5).unwrap() - 1.5).abs() < f32::EPSILON);
+        assert!(check_strict_f32(f64::NAN).unwrap().is_nan());
+        assert_eq!(check_strict_f32(f64::INFINITY).unwrap(), f32::INFINITY);
+        assert!(is_unable_to_coerce(
+            &check_strict_f32(1e300),
+            "f32",
+            "1e+300"
+        ));
+    }
+
+    #[test]
+    fn test_strict_types() {
+        let number = [JsType::Number];
+        assert!(check_strict_type(JsType::Number, &number, "u8").is_ok());
+        assert!(is_unable_to_coerce(
+            &check_strict_type(JsType::String, &number, "u8"),
+            "u8",
+            "string"
+        ));
+        assert!(is_unable_to_coerce(
+            &check_strict_type(JsType::Boolean, &number, "f64"),
+            "f64",
+            "boolean"
+        ));
+        // only a `date::JsDate` field reads a `Date`
+        assert!(is_unable_to_coerce(
+            &check_strict_type(JsType::Date, &number, "i64"),
+            "i64",
+            "Date"
+        ));
+
+        let boolean = [JsType::Boolean];
+        assert!(check_strict_type(JsType::Boolean, &boolean, "bool").is_ok());
+        assert!(is_unable_to_coerce(
+            &check_strict_type(JsType::Number, &boolean, "bool"),
+            "bool",
+            "number"
+        ));
+        assert!(is_unable_to_coerce(
+            &check_strict_type(JsType::String, &boolean, "bool"),
+            "bool",
+            "string"
+        ));
+
+        let unit = [JsType::Null, JsType::Undefined];
+        assert!(check_strict_type(JsType::Undefined, &unit, "unit").is_ok());
+        assert!(is_unable_to_coerce(
+            &check_strict_type(JsType::Object, &unit, "unit"),
+            "unit",
+            "object"
+        ));
+    }
+}
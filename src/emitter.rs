@@ -0,0 +1,293 @@
+//!
+//! Emit events to JS from any thread with serialized payloads
+//!
+
+use crate::callback::exception_message;
+use crate::errors::{Error as LibError, Result as LibResult};
+use crate::value::Value;
+use neon::event::Channel;
+use neon::prelude::*;
+use serde::Serialize;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+/// How many events an `Emitter` lets wait for the JS thread by default
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+enum Target {
+    /// Called as `listener(event, payload)`
+    Listener(Root<JsFunction>),
+    /// Called as `emitter.emit(event, payload)`
+    EventEmitter(Root<JsObject>),
+}
+
+/// Thrown by `Emitter::new` for a target that is not a `Target`
+pub(crate) const NOT_A_TARGET: &str = "expected a function or an EventEmitter";
+
+/// Whether `target` is a function or an object with an `emit` method
+pub(crate) fn is_target<'a, C: Context<'a>>(
+    cx: &mut C,
+    target: Handle<JsObject>,
+) -> NeonResult<bool> {
+    if target.is_a::<JsFunction, _>(cx) {
+        return Ok(true);
+    }
+    let emit = target.get_value(cx, "emit")?;
+    Ok(emit.is_a::<JsFunction, _>(cx))
+}
+
+#[derive(Default)]
+struct Queue {
+    /// Events sent to the JS thread and not delivered yet
+    pending: usize,
+    /// The message of the first failed delivery since the last report
+    error: Option<String>,
+}
+
+impl Queue {
+    fn report(&mut self) -> LibResult<()> {
+        match self.error.take() {
+            Some(msg) => Err(LibError::Callback { msg }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn wait_while<'a>(
+        &self,
+        queue: MutexGuard<'a, Queue>,
+        condition: impl FnMut(&mut Queue) -> bool,
+    ) -> MutexGuard<'a, Queue> {
+        self.changed
+            .wait_while(queue, condition)
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A queued event, counted as delivered when dropped, also when the JS
+/// thread shuts down before running it
+struct Pending(Arc<Shared>);
+
+impl Pending {
+    fn fail(&self, msg: String) {
+        self.0.lock().error.get_or_insert(msg);
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.0.lock().pending -= 1;
+        self.0.changed.notify_all();
+    }
+}
+
+/// A `Send + Clone` handle emitting events to a JS listener or
+/// `EventEmitter` from any thread
+///
+/// Payloads are serialized on the emitting thread and converted on the JS
+/// thread, at most `capacity` events wait for it: `emit` blocks until there
+/// is room and `try_emit` fails with `Error::QueueFull`. A listener that
+/// throws is reported as `Error::Callback` by the next `emit`, `try_emit`
+/// or `flush`. An `export!` param written as `Emitter` is read from a
+/// function or `EventEmitter` argument
+///
+/// ```rust,no_run
+/// # use neon_serde4 as neon_serde;
+/// use neon::prelude::*;
+/// use neon_serde::{export, Emitter};
+///
+/// #[derive(serde::Serialize)]
+/// struct Progress {
+///     done: usize,
+///     total: usize,
+/// }
+///
+/// export! {
+///     /// `events.on("progress", ({ done, total }) => ...)` from JS
+///     #[blocking]
+///     fn index(paths: Vec<String>, events: Emitter) -> usize {
+///         let total = paths.len();
+///         for done in 0..total {
+///             // stop when a listener threw
+///             if events.emit("progress", &Progress { done, total }).is_err() {
+///                 return done;
+///             }
+///         }
+///         // deliver the last events before the promise resolves
+///         events.flush().ok();
+///         total
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Emitter {
+    target: Arc<Target>,
+    shared: Arc<Shared>,
+    channel: Channel,
+    capacity: usize,
+}
+
+impl Emitter {
+    /// Roots `target`, a function is called as `target(event, payload)`
+    /// and any other object as `target.emit(event, payload)`, the emitter
+    /// does not keep the event loop alive
+    ///
+    /// # Errors
+    ///
+    /// * a `TypeError` if `target` is neither a function nor has an `emit` method
+    pub fn new<'a, C: Context<'a>>(cx: &mut C, target: Handle<JsObject>) -> NeonResult<Self> {
+        if !is_target(cx, target)? {
+            return cx.throw_type_error(NOT_A_TARGET);
+        }
+        let target = match target.downcast::<JsFunction, _>(cx) {
+            Ok(listener) => Target::Listener(listener.root(cx)),
+            Err(_) => Target::EventEmitter(target.root(cx)),
+        };
+        let mut channel = cx.channel();
+        channel.unref(cx);
+        Ok(Emitter {
+            target: Arc::new(target),
+            shared: Arc::default(),
+            channel,
+            capacity: DEFAULT_CAPACITY,
+        })
+    }
+
+    /// Lets at most `capacity` events wait for the JS thread, at least one
+    #[must_use]
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Serializes `payload` and queues the event, waiting while the queue
+    /// is full. Waiting on the JS thread deadlocks, use `try_emit` there
+    ///
+    /// # Errors
+    ///
+    /// * `Callback` if an earlier event could not be delivered, the event is
+    ///   not queued, or if it could not be scheduled on the JS thread
+    /// * any error of `Value::from_serialize`
+    pub fn emit<T: Serialize + ?Sized>(&self, event: &str, payload: &T) -> LibResult<()> {
+        let payload = Value::from_serialize(payload)?;
+        let queue = self.shared.wait_while(self.shared.lock(), |queue| {
+            queue.error.is_none() && queue.pending >= self.capacity
+        });
+        self.enqueue(queue, event, payload)
+    }
+
+    /// Like `emit` without waiting
+    ///
+    /// # Errors
+    ///
+    /// * `QueueFull` if `capacity` events are waiting for the JS thread
+    /// * any error of `emit`
+    pub fn try_emit<T: Serialize + ?Sized>(&self, event: &str, payload: &T) -> LibResult<()> {
+        let payload = Value::from_serialize(payload)?;
+        let queue = self.shared.lock();
+        if queue.error.is_none() && queue.pending >= self.capacity {
+            return Err(LibError::QueueFull {
+                capacity: self.capacity,
+            });
+        }
+        self.enqueue(queue, event, payload)
+    }
+
+    /// Waits until every queued event was delivered, calling it on the JS
+    /// thread deadlocks
+    ///
+    /// # Errors
+    ///
+    /// * `Callback` if an event could not be delivered
+    pub fn flush(&self) -> LibResult<()> {
+        let mut queue = self
+            .shared
+            .wait_while(self.shared.lock(), |queue| queue.pending > 0);
+        queue.report()
+    }
+
+    fn enqueue(&self, mut queue: MutexGuard<Queue>, event: &str, payload: Value) -> LibResult<()> {
+        queue.report()?;
+        // counted before unlocking so concurrent emits see a full queue
+        queue.pending += 1;
+        drop(queue);
+
+        let pending = Pending(Arc::clone(&self.shared));
+        let event = event.to_owned();
+        let target = Arc::clone(&self.target);
+        self.channel
+            .try_send(move |mut cx| {
+                if let Err(msg) = deliver(&mut cx, &target, &event, &payload) {
+                    pending.fail(msg);
+                }
+                Ok(())
+            })
+            .map(drop)
+            .map_err(|err| LibError::Callback {
+                msg: err.to_string(),
+            })
+    }
+}
+
+/// Calls the listener on the JS thread, neither a thrown exception nor
+/// `Error::Js` can leave it, errors are replaced by their message
+fn deliver(cx: &mut Cx, target: &Target, event: &str, payload: &Value) -> Result<(), String> {
+    let result = cx.try_catch(|cx| {
+        let event = cx.string(event).upcast::<JsValue>();
+        let payload = match payload.to_js(cx) {
+            Ok(payload) => payload,
+            Err(LibError::Js { throw }) => return Err(throw),
+            Err(err) => return Ok(Err(err.to_string())),
+        };
+        match target {
+            Target::Listener(listener) => {
+                let this = cx.undefined();
+                listener.to_inner(cx).call(cx, this, [event, payload])?;
+            }
+            Target::EventEmitter(emitter) => {
+                let emitter = emitter.to_inner(cx);
+                let emit: Handle<JsFunction> = emitter.get(cx, "emit")?;
+                emit.call(cx, emitter, [event, payload])?;
+            }
+        }
+        Ok(Ok(()))
+    });
+    result.unwrap_or_else(|exception| Err(exception_message(cx, exception)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending() {
+        let shared = Arc::new(Shared::default());
+        shared.lock().pending = 2;
+
+        let first = Pending(Arc::clone(&shared));
+        let second = Pending(Arc::clone(&shared));
+        first.fail("first".into());
+        second.fail("second".into());
+        drop(first);
+        assert_eq!(shared.lock().pending, 1);
+
+        // dropped without running, as on shutdown
+        drop(second);
+        let mut queue = shared.wait_while(shared.lock(), |queue| queue.pending > 0);
+        match queue.report() {
+            Err(LibError::Callback { msg }) => assert_eq!(msg, "first"),
+            other => panic!("expected the first failure, got {other:?}"),
+        }
+        assert!(queue.report().is_ok());
+    }
+}
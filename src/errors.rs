@@ -1,6 +1,7 @@
 //! Defines error handling types used by the create
 //! uses the `error-chain` create for generation
 
+use crate::promise::Rejection;
 use neon;
 use serde::{de, ser};
 use std::{convert::From, error, fmt, fmt::Display, result};
@@ -13,10 +14,16 @@ pub enum Error {
     /// trying to serialize a string that is too long will result in an error
     StringTooLong { len: usize },
 
-    /// when deserializing to a boolean `false` `undefined` `null` `number`
-    /// are valid inputs
-    /// any other types will result in error
-    UnableToCoerce { to_type: &'static str },
+    /// occurs when deserializing with `Coercion::Lenient` and the value
+    /// has no JS-like coercion to the target type, such as `"abc"` to a
+    /// number or `2` to a boolean, or with `Coercion::Strict` and the value
+    /// is not exactly the target type, such as `"1"` or `1.5` to a `u32`,
+    /// `found` is the JS type of the value, or the number or string itself
+    /// when its type fits but its value does not
+    UnableToCoerce {
+        to_type: &'static str,
+        found: String,
+    },
 
     /// occurs when deserializing a char from an empty string
     EmptyString,
@@ -33,6 +40,11 @@ pub enum Error {
     /// a none-1 number of properties
     InvalidKeyType { key: String },
 
+    /// occurs when serializing a map whose key can not be used as a
+    /// JS property name (anything other than a string, number, bool,
+    /// char or unit enum variant)
+    UnsupportedMapKey { key_type: &'static str },
+
     /// an internal deserialization error from an invalid array
     ArrayIndexOutOfBounds { index: u32, length: u32 },
 
@@ -51,6 +63,19 @@ pub enum Error {
 
     /// Generic deserialize error
     Deserialize { msg: String },
+
+    /// A `JsCallback` called from another thread or an `Emitter` listener
+    /// threw, with the message of the exception, or could not be scheduled
+    /// on the JS thread
+    Callback { msg: String },
+
+    /// occurs when `Emitter::try_emit` finds `capacity` events waiting
+    /// for the JS thread
+    QueueFull { capacity: usize },
+
+    /// A promise awaited with `from_promise` was rejected, `or_throw`
+    /// rethrows the reason, an object with a `message` as an `Error`
+    Rejected { reason: Rejection },
 }
 
 impl error::Error for Error {}
@@ -62,9 +87,11 @@ impl Display for Error {
                 "String too long for nodejs len: ".fmt(f)?;
                 len.fmt(f)
             }
-            Error::UnableToCoerce { to_type } => {
+            Error::UnableToCoerce { to_type, found } => {
                 "Unable to coerce value to type: ".fmt(f)?;
-                to_type.fmt(f)
+                to_type.fmt(f)?;
+                ", found: ".fmt(f)?;
+                found.fmt(f)
             }
             Error::EmptyString => "EmptyString".fmt(f),
             Error::StringTooLongForChar { len } => {
@@ -77,6 +104,11 @@ impl Display for Error {
                 key.fmt(f)?;
                 '\''.fmt(f)
             }
+            Error::UnsupportedMapKey { key_type } => {
+                "Unsupported map key type: '".fmt(f)?;
+                key_type.fmt(f)?;
+                "', expected a string, number, bool or char".fmt(f)
+            }
             Error::ArrayIndexOutOfBounds { index, length } => {
                 "Array index out of bounds (".fmt(f)?;
                 index.fmt(f)?;
@@ -99,6 +131,18 @@ impl Display for Error {
                 "Deserialize error: ".fmt(f)?;
                 msg.fmt(f)
             }
+            Error::Callback { msg } => {
+                "Callback error: ".fmt(f)?;
+                msg.fmt(f)
+            }
+            Error::QueueFull { capacity } => {
+                "Emitter queue full, capacity: ".fmt(f)?;
+                capacity.fmt(f)
+            }
+            Error::Rejected { reason } => {
+                "Promise rejected: ".fmt(f)?;
+                reason.message.fmt(f)
+            }
         }
     }
 }
@@ -129,19 +173,23 @@ use neon::context::Context;
 use neon::result::{NeonResult, ResultExt};
 
 impl<T> ResultExt<T> for Error {
+    #[allow(clippy::enum_glob_use)]
     fn or_throw<'a, C: Context<'a>>(self, cx: &mut C) -> NeonResult<T> {
         use Error::*;
         match self {
             StringTooLong { len } => cx.throw_error(format!("string too long: {len}")),
-            UnableToCoerce { to_type } => {
-                cx.throw_type_error(format!("unable to coerce: {to_type}"))
-            }
+            UnableToCoerce { to_type, found } => cx.throw_type_error(format!(
+                "unable to coerce: expected {to_type}, found {found}"
+            )),
             EmptyString => cx.throw_error("unexpected empty string"),
             StringTooLongForChar { len } => {
                 cx.throw_error(format!("string too long for char, length: {len}"))
             }
             ExpectingNull => cx.throw_type_error("expecting null"),
             InvalidKeyType { key } => cx.throw_type_error(format!("invalid key type: {key}")),
+            UnsupportedMapKey { key_type } => {
+                cx.throw_type_error(format!("unsupported map key type: {key_type}"))
+            }
             ArrayIndexOutOfBounds { index, length } => cx.throw_range_error(format!(
                 "array index out of bounds: index={index}, length={length}"
             )),
@@ -150,6 +198,15 @@ impl<T> ResultExt<T> for Error {
             CastError => cx.throw_type_error("cast error"),
             Serialize { msg } => cx.throw_type_error(format!("unable to serialize: {msg}")),
             Deserialize { msg } => cx.throw_type_error(format!("unable to deserialize: {msg}")),
+            Callback { msg } => cx.throw_error(format!("callback failed: {msg}")),
+            QueueFull { capacity } => {
+                cx.throw_error(format!("emitter queue full, capacity: {capacity}"))
+            }
+            Rejected { reason } => match reason.to_js(cx) {
+                Ok(reason) => cx.throw(reason),
+                Err(Js { throw }) => Err(throw),
+                Err(_) => cx.throw_error(reason.message),
+            },
         }
     }
 }
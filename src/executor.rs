@@ -0,0 +1,211 @@
+//!
+//! Runs the futures of `async fn`s exported with `export!`
+//!
+//! By default futures are polled on a shared pool with one thread per CPU,
+//! started when the first future is spawned. The pool only polls: it has no
+//! I/O reactor or timers, so a future that needs a runtime, such as tokio's
+//! `sleep` or sockets, panics or never wakes there. Hand such futures to
+//! that runtime with `set_spawner`, and keep blocking calls out of `async fn`s,
+//! they hold a pool thread, `#[blocking]` runs them on the libuv thread pool
+//!
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once, OnceLock, PoisonError};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+
+/// A future handed to the executor
+pub type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+type Spawner = Box<dyn Fn(Task) + Send + Sync>;
+
+static SPAWNER: OnceLock<Spawner> = OnceLock::new();
+
+/// Spawns every later future with `spawner` instead of the built-in pool,
+/// futures already spawned keep running on the pool. Call it before the
+/// first `async fn` runs, from `#[neon::main]` with `#![register]` blocks
+///
+/// ```rust,ignore
+/// #[neon::main]
+/// fn main(mut cx: ModuleContext) -> NeonResult<()> {
+///     let runtime = tokio::runtime::Runtime::new().or_else(|err| cx.throw_error(err.to_string()))?;
+///     let handle = runtime.handle().clone();
+///     // the runtime lives as long as the module
+///     std::mem::forget(runtime);
+///     neon_serde::executor::set_spawner(move |task| drop(handle.spawn(task))).ok();
+///     api::register(&mut cx)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// * `spawner` is given back if a spawner was set before
+pub fn set_spawner<F>(spawner: F) -> Result<(), F>
+where
+    F: Fn(Task) + Send + Sync + 'static,
+{
+    static SETTING: Mutex<()> = Mutex::new(());
+
+    let _setting = SETTING.lock().unwrap_or_else(PoisonError::into_inner);
+    if SPAWNER.get().is_some() {
+        return Err(spawner);
+    }
+    // only set while holding `SETTING`, it can not fail
+    let _ = SPAWNER.set(Box::new(spawner));
+    Ok(())
+}
+
+/// Runs `future` on the spawner set with `set_spawner` or the built-in pool
+///
+/// # Panics
+///
+/// * if the pool is started and no thread can be spawned
+pub fn spawn<F>(future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let task: Task = Box::pin(future);
+    if let Some(spawner) = SPAWNER.get() {
+        spawner(task);
+    } else {
+        let pool = Pool::get();
+        pool.schedule(Arc::new(Job {
+            future: Mutex::new(Some(task)),
+            pool,
+        }));
+    }
+}
+
+/// The built-in pool, its threads poll the jobs that were woken
+#[derive(Default)]
+struct Pool {
+    queue: Mutex<VecDeque<Arc<Job>>>,
+    ready: Condvar,
+}
+
+impl Pool {
+    fn get() -> &'static Pool {
+        static POOL: OnceLock<Pool> = OnceLock::new();
+        static START: Once = Once::new();
+
+        let pool = POOL.get_or_init(Pool::default);
+        START.call_once(|| {
+            let threads = thread::available_parallelism().map_or(4, NonZeroUsize::get);
+            for i in 0..threads {
+                thread::Builder::new()
+                    .name(format!("neon-serde-executor-{i}"))
+                    .spawn(|| pool.work())
+                    .expect("failed to start an executor thread");
+            }
+        });
+        pool
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<Arc<Job>>> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn schedule(&self, job: Arc<Job>) {
+        self.lock().push_back(job);
+        self.ready.notify_one();
+    }
+
+    fn work(&self) {
+        loop {
+            let job = self
+                .ready
+                .wait_while(self.lock(), |queue| queue.is_empty())
+                .unwrap_or_else(PoisonError::into_inner)
+                .pop_front();
+            if let Some(job) = job {
+                job.run();
+            }
+        }
+    }
+}
+
+/// A spawned future, scheduled again on the pool when woken
+struct Job {
+    /// `None` once the future completed or panicked
+    future: Mutex<Option<Task>>,
+    pool: &'static Pool,
+}
+
+impl Job {
+    fn run(self: Arc<Self>) {
+        let waker = Waker::from(Arc::clone(&self));
+        let mut future = self.future.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(task) = future.as_mut() else {
+            // woken again after it completed
+            return;
+        };
+        let mut cx = Context::from_waker(&waker);
+        // a panic drops the future and leaves the thread to the other jobs
+        let polled = panic::catch_unwind(AssertUnwindSafe(|| task.as_mut().poll(&mut cx)));
+        if !matches!(polled, Ok(Poll::Pending)) {
+            *future = None;
+        }
+    }
+}
+
+impl Wake for Job {
+    fn wake(self: Arc<Self>) {
+        self.pool.schedule(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spawn;
+    use std::future::poll_fn;
+    use std::sync::mpsc;
+    use std::task::Poll;
+    use std::thread;
+
+    #[test]
+    fn test_spawned_futures_complete() {
+        let (sender, receiver) = mpsc::channel();
+        for i in 0..16 {
+            let sender = sender.clone();
+            spawn(async move {
+                sender.send(i).unwrap();
+            });
+        }
+        let mut done: Vec<i32> = receiver.iter().take(16).collect();
+        done.sort_unstable();
+        assert_eq!(done, (0..16).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_woken_futures_are_polled_again() {
+        let (sender, receiver) = mpsc::channel();
+        let mut polls = 0;
+        spawn(async move {
+            // woken from another thread, twice
+            poll_fn(|cx| {
+                polls += 1;
+                if polls == 3 {
+                    return Poll::Ready(());
+                }
+                let waker = cx.waker().clone();
+                thread::spawn(move || waker.wake());
+                Poll::Pending
+            })
+            .await;
+            sender.send(polls).unwrap();
+        });
+        assert_eq!(receiver.recv().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_panics_leave_the_pool_running() {
+        spawn(async { panic!("in a spawned future") });
+        let (sender, receiver) = mpsc::channel();
+        spawn(async move { sender.send(()).unwrap() });
+        receiver.recv().unwrap();
+    }
+}
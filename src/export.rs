@@ -0,0 +1,509 @@
+//!
+//! Runtime support for the `export!` macro
+//!
+//! Exported functions may return any `Serialize` value, a `Result<T, E>` or
+//! a `JsResult`, the macro picks the conversion below with autoref
+//! specialization: the receiver is `&&&&&Returned<R>` and the most specific
+//! trait that matches wins
+//!
+
+use crate::callback::JsCallback;
+use crate::de::{from_value, from_value_opt};
+use crate::emitter::{self, Emitter};
+use crate::errors::Error as LibError;
+use crate::ser::to_value;
+use neon::context::CallKind;
+use neon::prelude::*;
+use neon::result::ResultExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::Any;
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::fmt::Display;
+use std::future::{poll_fn, Future};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::pin;
+use std::task::Poll;
+use std::thread;
+
+/// Wraps the value returned by an exported function, the conversions only
+/// get `&self` so the value is moved out of a `Cell`
+#[doc(hidden)]
+pub struct Returned<R>(Cell<Option<R>>);
+
+impl<R> Returned<R> {
+    pub fn new(value: R) -> Self {
+        Returned(Cell::new(Some(value)))
+    }
+
+    fn take(&self) -> R {
+        self.0.take().expect("returned value converted twice")
+    }
+}
+
+/// A `JsResult` built by the function itself is passed through
+#[doc(hidden)]
+pub trait ToJsHandle<'j> {
+    fn to_js<C: Context<'j>>(&self, cx: &mut C) -> JsResult<'j, JsValue>;
+}
+
+/// `Ok` is serialized, an `Error::Js` is rethrown, such as an exception of a
+/// `JsCallback`, other errors of this crate are thrown like `or_throw`
+#[doc(hidden)]
+pub trait ToJsLibResult {
+    fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> JsResult<'j, JsValue>;
+}
+
+/// `Ok` is serialized, `Err` is thrown as an `Error` carrying the serialized error
+#[doc(hidden)]
+pub trait ToJsStructuredResult {
+    fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> JsResult<'j, JsValue>;
+}
+
+/// `Ok` is serialized, `Err` is thrown as an `Error` with its `Display` message
+#[doc(hidden)]
+pub trait ToJsResult {
+    fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> JsResult<'j, JsValue>;
+}
+
+/// Any other `Serialize` value is returned as is
+#[doc(hidden)]
+pub trait ToJsValue {
+    fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> JsResult<'j, JsValue>;
+}
+
+impl<'j, V: Value> ToJsHandle<'j> for &&&&Returned<JsResult<'j, V>> {
+    fn to_js<C: Context<'j>>(&self, _cx: &mut C) -> JsResult<'j, JsValue> {
+        self.take().map(|handle| handle.upcast())
+    }
+}
+
+impl<T: Serialize> ToJsLibResult for &&&Returned<Result<T, LibError>> {
+    fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> JsResult<'j, JsValue> {
+        match self.take() {
+            Ok(value) => to_value(cx, &value).or_else(|err| err.or_throw(cx)),
+            Err(err) => err.or_throw(cx),
+        }
+    }
+}
+
+impl<T: Serialize, E: Display + Serialize> ToJsStructuredResult for &&Returned<Result<T, E>> {
+    fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> JsResult<'j, JsValue> {
+        match self.take() {
+            Ok(value) => to_value(cx, &value).or_else(|err| err.or_throw(cx)),
+            Err(err) => throw_structured(cx, &err),
+        }
+    }
+}
+
+impl<T: Serialize, E: Display> ToJsResult for &Returned<Result<T, E>> {
+    fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> JsResult<'j, JsValue> {
+        match self.take() {
+            Ok(value) => to_value(cx, &value).or_else(|err| err.or_throw(cx)),
+            Err(err) => cx.throw_error(err.to_string()),
+        }
+    }
+}
+
+impl<R: Serialize> ToJsValue for Returned<R> {
+    fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> JsResult<'j, JsValue> {
+        to_value(cx, &self.take()).or_else(|err| err.or_throw(cx))
+    }
+}
+
+/// Throws an `Error` whose message is the `Display` of `err`, when `err`
+/// serializes to an object its properties are copied onto the error
+/// (so `err.code` works from JS), any other value is set as `error.data`
+fn throw_structured<'j, C, E>(cx: &mut C, err: &E) -> JsResult<'j, JsValue>
+where
+    C: Context<'j>,
+    E: Display + Serialize,
+{
+    let error = JsError::error(cx, err.to_string())?;
+    let data = to_value(cx, err).or_else(|err: LibError| err.or_throw(cx))?;
+
+    match data.downcast::<JsObject, _>(cx) {
+        Ok(obj) if !data.is_a::<JsArray, _>(cx) => {
+            let keys = obj.get_own_property_names(cx)?;
+            for i in 0..keys.len(cx) {
+                let key: Handle<JsValue> = keys.get(cx, i)?;
+                let value: Handle<JsValue> = obj.get(cx, key)?;
+                error.set(cx, key, value)?;
+            }
+        }
+        _ => {
+            error.set(cx, "data", data)?;
+        }
+    }
+
+    cx.throw(error)
+}
+
+/// Where the params of an exported function are read from, its positional
+/// arguments or, with `#[options]`, the properties of a single object
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub enum Params<'j> {
+    Arguments,
+    Options(Handle<'j, JsObject>),
+}
+
+impl<'j> Params<'j> {
+    /// Reads the options object of the exported function `function`,
+    /// a missing or `undefined` object is treated as `{}`
+    pub fn options(cx: &mut FunctionContext<'j>, function: &str) -> NeonResult<Self> {
+        match cx.argument_opt(0) {
+            Some(value) if !value.is_a::<JsUndefined, _>(cx) => value
+                .downcast::<JsObject, _>(cx)
+                .map(Params::Options)
+                .or_else(|_| {
+                    cx.throw_type_error(format!("'{function}' expected an options object"))
+                }),
+            _ => Ok(Params::Options(cx.empty_object())),
+        }
+    }
+
+    fn get(
+        self,
+        cx: &mut FunctionContext<'j>,
+        name: &str,
+        index: usize,
+    ) -> NeonResult<Option<Handle<'j, JsValue>>> {
+        match self {
+            Params::Arguments => Ok(cx.argument_opt(index)),
+            Params::Options(options) => options.get_value(cx, name).map(Some),
+        }
+    }
+
+    fn describe(self, name: &str, index: usize) -> String {
+        match self {
+            Params::Arguments => format!("argument '{name}' (#{index})"),
+            Params::Options(_) => format!("option '{name}'"),
+        }
+    }
+
+    fn throw_invalid<T>(
+        self,
+        cx: &mut FunctionContext<'j>,
+        name: &str,
+        index: usize,
+        err: LibError,
+    ) -> NeonResult<T> {
+        let param = self.describe(name, index);
+        match err {
+            LibError::Deserialize { msg } => cx.throw_type_error(format!("{param}: {msg}")),
+            err => cx.throw_type_error(format!("{param}: {err}")),
+        }
+    }
+
+    fn throw_missing<T>(
+        self,
+        cx: &mut FunctionContext<'j>,
+        function: &str,
+        name: &str,
+        index: usize,
+    ) -> NeonResult<T> {
+        let len = cx.len();
+        match self {
+            Params::Arguments if index >= len => cx.throw_type_error(format!(
+                "'{function}' expected at least {} arguments but got {len}, missing '{name}'",
+                index + 1
+            )),
+            Params::Arguments => {
+                cx.throw_type_error(format!("'{function}' missing argument '{name}' (#{index})"))
+            }
+            Params::Options(_) => {
+                cx.throw_type_error(format!("'{function}' missing option '{name}'"))
+            }
+        }
+    }
+}
+
+/// Reads the param `name` of the exported function `function` and
+/// deserializes it, errors name the param as `argument 'user' (#0): ...`,
+/// `default` is used when the param is `undefined`
+#[doc(hidden)]
+pub fn argument<'j, T, D>(
+    cx: &mut FunctionContext<'j>,
+    params: Params<'j>,
+    function: &str,
+    name: &str,
+    index: usize,
+    default: Option<D>,
+) -> NeonResult<T>
+where
+    T: DeserializeOwned,
+    D: FnOnce() -> T,
+{
+    // `undefined` is treated as missing
+    let value = match params.get(cx, name, index)? {
+        Some(value) if !value.is_a::<JsUndefined, _>(cx) => Some(value),
+        _ => None,
+    };
+    if let (None, Some(default)) = (value, default) {
+        return Ok(default());
+    }
+    match from_value_opt(cx, value) {
+        Ok(value) => Ok(value),
+        Err(LibError::Js { throw }) => Err(throw),
+        // a missing param that is not an `Option`
+        Err(_) if value.is_none() => params.throw_missing(cx, function, name, index),
+        Err(err) => params.throw_invalid(cx, name, index, err),
+    }
+}
+
+/// Collects the arguments of the exported function `function` from `index`
+/// on into a `#[rest]` param, each deserialized on its own, with `#[options]`
+/// the param is read as an array
+#[doc(hidden)]
+pub fn rest_arguments<'j, T>(
+    cx: &mut FunctionContext<'j>,
+    params: Params<'j>,
+    function: &str,
+    name: &str,
+    index: usize,
+) -> NeonResult<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    if let Params::Options(_) = params {
+        return argument(cx, params, function, name, index, Some(Vec::new));
+    }
+
+    let mut rest = Vec::with_capacity(cx.len().saturating_sub(index));
+    for i in index..cx.len() {
+        let value = cx.argument::<JsValue>(i)?;
+        match from_value(cx, value) {
+            Ok(value) => rest.push(value),
+            Err(LibError::Js { throw }) => return Err(throw),
+            Err(err) => return params.throw_invalid(cx, name, i, err),
+        }
+    }
+    Ok(rest)
+}
+
+/// Reads the param `name` of the exported function `function` as a `Handle<V>`
+#[doc(hidden)]
+pub fn handle_argument<'j, V>(
+    cx: &mut FunctionContext<'j>,
+    params: Params<'j>,
+    function: &str,
+    name: &str,
+    index: usize,
+) -> JsResult<'j, V>
+where
+    V: Value,
+{
+    let Some(value) = params.get(cx, name, index)? else {
+        return params.throw_missing(cx, function, name, index);
+    };
+    value
+        .downcast::<V, _>(cx)
+        .or_else(|err| cx.throw_type_error(format!("{}: {err}", params.describe(name, index))))
+}
+
+/// Reads the param `name` of the exported function `function` as a rooted
+/// `JsCallback`
+#[doc(hidden)]
+pub fn callback_argument<'j, Args, Ret>(
+    cx: &mut FunctionContext<'j>,
+    params: Params<'j>,
+    function: &str,
+    name: &str,
+    index: usize,
+) -> NeonResult<JsCallback<Args, Ret>> {
+    let callback = handle_argument::<JsFunction>(cx, params, function, name, index)?;
+    Ok(JsCallback::new(cx, callback))
+}
+
+/// Reads the param `name` of the exported function `function` as an
+/// `Emitter` of a listener or `EventEmitter`
+#[doc(hidden)]
+pub fn emitter_argument<'j>(
+    cx: &mut FunctionContext<'j>,
+    params: Params<'j>,
+    function: &str,
+    name: &str,
+    index: usize,
+) -> NeonResult<Emitter> {
+    let value = handle_argument::<JsValue>(cx, params, function, name, index)?;
+    // a function is not a `JsObject` to `downcast`
+    let target = match value.downcast::<JsFunction, _>(cx) {
+        Ok(listener) => Some(listener.upcast::<JsObject>()),
+        Err(_) => value.downcast::<JsObject, _>(cx).ok(),
+    };
+    match target {
+        Some(target) if emitter::is_target(cx, target)? => Emitter::new(cx, target),
+        _ => {
+            let param = params.describe(name, index);
+            cx.throw_type_error(format!("{param}: {}", emitter::NOT_A_TARGET))
+        }
+    }
+}
+
+/// Throws a `TypeError` when the exported function `function` was called
+/// with more than `max` arguments
+#[doc(hidden)]
+pub fn check_arity(cx: &mut FunctionContext, function: &str, max: usize) -> NeonResult<()> {
+    let len = cx.len();
+    if len > max {
+        return cx.throw_type_error(format!(
+            "'{function}' expected at most {max} arguments but got {len}"
+        ));
+    }
+    Ok(())
+}
+
+/// Serializes the `const` item `name` and sets it on `exports`, a `#[frozen]`
+/// value is deeply frozen with `Object.freeze`
+#[doc(hidden)]
+pub fn constant<'a, C, T>(
+    cx: &mut C,
+    exports: Handle<'a, JsObject>,
+    name: &str,
+    value: &T,
+    frozen: bool,
+) -> NeonResult<()>
+where
+    C: Context<'a>,
+    T: Serialize + ?Sized,
+{
+    let value = to_value(cx, value).or_else(|err| err.or_throw(cx))?;
+    if frozen {
+        freeze(cx, value)?;
+    }
+    exports.set(cx, name, value)?;
+    Ok(())
+}
+
+fn freeze<'a, C: Context<'a>>(cx: &mut C, value: Handle<'a, JsValue>) -> NeonResult<()> {
+    // a `Buffer` with elements cannot be frozen
+    if value.is_a::<JsBuffer, _>(cx) {
+        return Ok(());
+    }
+    let Ok(obj) = value.downcast::<JsObject, _>(cx) else {
+        return Ok(());
+    };
+
+    let keys = obj.get_own_property_names(cx)?;
+    for i in 0..keys.len(cx) {
+        let key: Handle<JsValue> = keys.get(cx, i)?;
+        let property: Handle<JsValue> = obj.get(cx, key)?;
+        freeze(cx, property)?;
+    }
+
+    let object: Handle<JsFunction> = cx.global("Object")?;
+    let object_freeze: Handle<JsFunction> = object.get(cx, "freeze")?;
+    object_freeze.call_with(cx).this(object).arg(obj).exec(cx)
+}
+
+/// The value of a class exported by `export!`, boxed on each instance
+#[doc(hidden)]
+pub struct Instance<T>(RefCell<T>);
+
+impl<T> Finalize for Instance<T> {}
+
+impl<T> Instance<T> {
+    /// Borrows the value for the `&self` method `method`
+    pub fn borrow<'a>(&'a self, cx: &mut FunctionContext, method: &str) -> NeonResult<Ref<'a, T>> {
+        self.0.try_borrow().or_else(|_| {
+            cx.throw_error(format!(
+                "'{method}' called while the instance is mutably borrowed"
+            ))
+        })
+    }
+
+    /// Borrows the value for the `&mut self` method `method`
+    pub fn borrow_mut<'a>(
+        &'a self,
+        cx: &mut FunctionContext,
+        method: &str,
+    ) -> NeonResult<RefMut<'a, T>> {
+        self.0.try_borrow_mut().or_else(|_| {
+            cx.throw_error(format!("'{method}' called while the instance is borrowed"))
+        })
+    }
+}
+
+/// The property of `this` holding the `JsBox<Instance<T>>`
+const INSTANCE: &str = "__neon_serde_instance";
+
+/// Throws a `TypeError` when the constructor of `class` is called without `new`
+#[doc(hidden)]
+pub fn check_construct(cx: &mut FunctionContext, class: &str) -> NeonResult<()> {
+    if matches!(cx.kind(), CallKind::Construct) {
+        Ok(())
+    } else {
+        cx.throw_type_error(format!(
+            "class constructor '{class}' cannot be invoked without 'new'"
+        ))
+    }
+}
+
+/// Boxes `value` onto the object being constructed, as a non-enumerable property
+#[doc(hidden)]
+pub fn construct<T: 'static>(cx: &mut FunctionContext, value: T) -> NeonResult<()> {
+    let this = cx.this::<JsObject>()?;
+    let instance = cx.boxed(Instance(RefCell::new(value)));
+    let descriptor = cx.empty_object();
+    descriptor.set(cx, "value", instance)?;
+
+    // `Object` is a function, it does not downcast to `JsObject`
+    let object: Handle<JsFunction> = cx.global("Object")?;
+    let define_property: Handle<JsFunction> = object.get(cx, "defineProperty")?;
+    let key = cx.string(INSTANCE);
+    define_property
+        .call_with(cx)
+        .this(object)
+        .arg(this)
+        .arg(key)
+        .arg(descriptor)
+        .exec(cx)
+}
+
+/// Reads the value boxed by `construct` from `this` for the method `method`
+#[doc(hidden)]
+pub fn this_instance<'j, T: 'static>(
+    cx: &mut FunctionContext<'j>,
+    method: &str,
+) -> JsResult<'j, JsBox<Instance<T>>> {
+    let this = cx.this_value();
+    let instance = match this.downcast::<JsObject, _>(cx) {
+        Ok(this) => this.get_value(cx, INSTANCE)?,
+        Err(_) => cx.undefined().upcast(),
+    };
+    instance
+        .downcast::<JsBox<Instance<T>>, _>(cx)
+        .or_else(|_| cx.throw_type_error(format!("'{method}' called on an incompatible receiver")))
+}
+
+/// Throws an `Error` for a panic caught while running the exported function `name`
+#[doc(hidden)]
+pub fn throw_panic<'j, C, T>(cx: &mut C, name: &str, panic: &(dyn Any + Send)) -> NeonResult<T>
+where
+    C: Context<'j>,
+{
+    let msg = if let Some(msg) = panic.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = panic.downcast_ref::<String>() {
+        msg.as_str()
+    } else {
+        "Box<dyn Any>"
+    };
+    cx.throw_error(format!("'{name}' panicked: {msg}"))
+}
+
+/// Resolves to the output of `future` or to the panic that interrupted it,
+/// like `std::panic::catch_unwind`
+#[doc(hidden)]
+pub async fn catch_unwind<F: Future>(future: F) -> thread::Result<F::Output> {
+    let mut future = pin!(future);
+    poll_fn(
+        |cx| match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(panic) => Poll::Ready(Err(panic)),
+        },
+    )
+    .await
+}
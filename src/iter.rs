@@ -0,0 +1,391 @@
+//!
+//! Expose Rust iterators and streams to JS, items are serialized one at a
+//! time as `next()` is called
+//!
+
+use crate::ser::to_value;
+use neon::prelude::*;
+use neon::result::ResultExt;
+#[cfg(feature = "futures")]
+use neon::types::Deferred;
+use serde::Serialize;
+use std::cell::RefCell;
+#[cfg(feature = "futures")]
+use std::collections::VecDeque;
+use std::rc::Rc;
+#[cfg(feature = "futures")]
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+#[cfg(feature = "futures")]
+use std::task::{Context as TaskContext, Poll, Waker};
+
+/// Returns a JS iterator over `iter`, each item is serialized with
+/// `to_value` when `next()` asks for it instead of collecting them all
+/// into an array
+///
+/// The object is its own `[Symbol.iterator]` so it works with `for...of`,
+/// spreading and `Array.from`, `return()`, called when a loop exits early,
+/// drops `iter`
+///
+/// ```rust,no_run
+/// # use neon_serde4 as neon_serde;
+/// use neon::prelude::*;
+/// use std::io::{BufRead, BufReader};
+///
+/// fn read_lines(mut cx: FunctionContext) -> JsResult<JsObject> {
+///     let path = cx.argument::<JsString>(0)?.value(&mut cx);
+///     let file = std::fs::File::open(path).or_else(|err| cx.throw_error(err.to_string()))?;
+///     let lines = BufReader::new(file).lines().map_while(Result::ok);
+///     neon_serde::to_iterator(&mut cx, lines)
+/// }
+/// ```
+///
+/// # Errors
+///
+/// * `next()` throws a `TypeError` if an item can not be serialized
+pub fn to_iterator<'a, C, I>(cx: &mut C, iter: I) -> JsResult<'a, JsObject>
+where
+    C: Context<'a>,
+    I: IntoIterator,
+    I::IntoIter: 'static,
+    I::Item: Serialize,
+{
+    let items = Rc::new(RefCell::new(Items::new(iter.into_iter())));
+
+    let next = {
+        let items = Rc::clone(&items);
+        JsFunction::new(cx, move |mut cx| {
+            let item = items.borrow_mut().next();
+            let value = match item {
+                Some(item) => Some(to_value(&mut cx, &item).or_else(|err| err.or_throw(&mut cx))?),
+                None => None,
+            };
+            step(&mut cx, value)
+        })?
+    };
+    let finish = JsFunction::new(cx, move |mut cx| {
+        items.borrow_mut().finish();
+        step(&mut cx, None)
+    })?;
+
+    let object = cx.empty_object();
+    object.set(cx, "next", next)?;
+    object.set(cx, "return", finish)?;
+    let iterator = well_known_symbol(cx, "iterator")?;
+    let this = JsFunction::new(cx, |mut cx| cx.this::<JsValue>())?;
+    object.set(cx, iterator, this)?;
+    Ok(object)
+}
+
+/// Returns a JS async iterator over `stream`, each item is serialized when
+/// the promise of `next()` resolves
+///
+/// The stream is polled on the `executor`, one item for each call to
+/// `next()`, and the object is its own `[Symbol.asyncIterator]` so it works
+/// with `for await...of`. `return()` drops `stream` once the pending calls
+/// settled. Node only waits for the stream while a call is pending
+///
+/// ```rust,no_run
+/// # use neon_serde4 as neon_serde;
+/// use futures_core::Stream;
+/// use neon::prelude::*;
+/// use std::pin::Pin;
+/// use std::task::{Context as TaskContext, Poll};
+///
+/// /// pages of results, each one fetched when JS asks for it
+/// struct Pages {
+///     next: u32,
+///     last: u32,
+/// }
+///
+/// impl Stream for Pages {
+///     type Item = Vec<u32>;
+///
+///     fn poll_next(mut self: Pin<&mut Self>, _: &mut TaskContext) -> Poll<Option<Vec<u32>>> {
+///         if self.next > self.last {
+///             return Poll::Ready(None);
+///         }
+///         let page = self.next;
+///         self.next += 1;
+///         Poll::Ready(Some((page * 10..page * 10 + 10).collect()))
+///     }
+/// }
+///
+/// fn pages(mut cx: FunctionContext) -> JsResult<JsObject> {
+///     let last = cx.argument::<JsNumber>(0)?.value(&mut cx) as u32;
+///     neon_serde::to_async_iterator(&mut cx, Pages { next: 0, last })
+/// }
+/// ```
+///
+/// # Errors
+///
+/// * the promise of `next()` is rejected with a `TypeError` if an item can
+///   not be serialized
+#[cfg(feature = "futures")]
+pub fn to_async_iterator<'a, C, S>(cx: &mut C, stream: S) -> JsResult<'a, JsObject>
+where
+    C: Context<'a>,
+    S: futures_core::Stream + Send + 'static,
+    S::Item: Serialize,
+{
+    use crate::value::Value;
+    use std::future::poll_fn;
+
+    let mut channel = cx.channel();
+    channel.unref(cx);
+    let inbox = Arc::new(Mutex::new(Inbox::default()));
+
+    let driver = Arc::clone(&inbox);
+    crate::executor::spawn(async move {
+        let mut stream = Some(Box::pin(stream));
+        // each request holds a referenced channel, dropping it after the
+        // promise settled lets node exit again
+        while let Some((deferred, channel)) = poll_fn(|cx| Inbox::poll_request(&driver, cx)).await {
+            let item = match stream.as_mut() {
+                Some(stream) => poll_fn(|cx| stream.as_mut().poll_next(cx)).await,
+                None => None,
+            };
+            if item.is_none() {
+                stream = None;
+            }
+            // `Error` is not `Send`, only its message is sent to the JS thread
+            let item = item.map(|item| Value::from_serialize(&item).map_err(|err| err.to_string()));
+            deferred.settle_with(&channel, move |mut cx| match item {
+                Some(Ok(value)) => {
+                    let value = value.to_js(&mut cx).or_else(|err| err.or_throw(&mut cx))?;
+                    step(&mut cx, Some(value))
+                }
+                Some(Err(msg)) => cx.throw_type_error(msg),
+                None => step(&mut cx, None),
+            });
+        }
+    });
+
+    // the inbox is closed by `return()` or when both functions are collected
+    let requests = Rc::new(RefCell::new(Some(Requests(inbox))));
+
+    let next = {
+        let requests = Rc::clone(&requests);
+        JsFunction::new(cx, move |mut cx| {
+            let (deferred, promise) = cx.promise();
+            if let Some(requests) = &*requests.borrow() {
+                let mut channel = channel.clone();
+                channel.reference(&mut cx);
+                requests.send(deferred, channel);
+                return Ok(promise);
+            }
+            let done = step(&mut cx, None)?;
+            deferred.resolve(&mut cx, done);
+            Ok(promise)
+        })?
+    };
+    let finish = JsFunction::new(cx, move |mut cx| {
+        requests.borrow_mut().take();
+        let (deferred, promise) = cx.promise();
+        let done = step(&mut cx, None)?;
+        deferred.resolve(&mut cx, done);
+        Ok(promise)
+    })?;
+
+    let object = cx.empty_object();
+    object.set(cx, "next", next)?;
+    object.set(cx, "return", finish)?;
+    let iterator = well_known_symbol(cx, "asyncIterator")?;
+    let this = JsFunction::new(cx, |mut cx| cx.this::<JsValue>())?;
+    object.set(cx, iterator, this)?;
+    Ok(object)
+}
+
+/// The calls to `next()` of an async iterator waiting for the driver future
+#[cfg(feature = "futures")]
+#[derive(Default)]
+struct Inbox {
+    queue: VecDeque<(Deferred, Channel)>,
+    /// set once no more calls come, the driver stops after the queued ones
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+#[cfg(feature = "futures")]
+impl Inbox {
+    fn lock(inbox: &Mutex<Inbox>) -> MutexGuard<'_, Inbox> {
+        inbox.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The next call, `None` once the inbox is closed and empty
+    fn poll_request(
+        inbox: &Mutex<Inbox>,
+        cx: &mut TaskContext,
+    ) -> Poll<Option<(Deferred, Channel)>> {
+        let mut inbox = Inbox::lock(inbox);
+        match inbox.queue.pop_front() {
+            Some(request) => Poll::Ready(Some(request)),
+            None if inbox.closed => Poll::Ready(None),
+            None => {
+                inbox.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The JS side of an `Inbox`, dropping it closes the inbox
+#[cfg(feature = "futures")]
+struct Requests(Arc<Mutex<Inbox>>);
+
+#[cfg(feature = "futures")]
+impl Requests {
+    fn send(&self, deferred: Deferred, channel: Channel) {
+        let waker = {
+            let mut inbox = Inbox::lock(&self.0);
+            inbox.queue.push_back((deferred, channel));
+            inbox.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl Drop for Requests {
+    fn drop(&mut self) {
+        let waker = {
+            let mut inbox = Inbox::lock(&self.0);
+            inbox.closed = true;
+            inbox.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// The state of `to_iterator`, an exhausted iterator is dropped right away
+/// and never polled again
+struct Items<I> {
+    iter: Option<I>,
+}
+
+impl<I: Iterator> Items<I> {
+    fn new(iter: I) -> Self {
+        Items { iter: Some(iter) }
+    }
+
+    /// The next item, `None` once the iterator is exhausted or finished
+    fn next(&mut self) -> Option<I::Item> {
+        let item = self.iter.as_mut().and_then(Iterator::next);
+        if item.is_none() {
+            self.iter = None;
+        }
+        item
+    }
+
+    /// Drops the iterator, `return()` ends the iteration early
+    fn finish(&mut self) {
+        self.iter = None;
+    }
+}
+
+/// An iterator result, `done` once there is no value
+fn step<'a, C: Context<'a>>(
+    cx: &mut C,
+    value: Option<Handle<'a, JsValue>>,
+) -> JsResult<'a, JsObject> {
+    let result = cx.empty_object();
+    let done = cx.boolean(value.is_none());
+    let value = value.unwrap_or_else(|| cx.undefined().upcast());
+    result.set(cx, "value", value)?;
+    result.set(cx, "done", done)?;
+    Ok(result)
+}
+
+/// `Symbol[name]`, such as `Symbol.iterator`
+fn well_known_symbol<'a, C: Context<'a>>(cx: &mut C, name: &str) -> JsResult<'a, JsValue> {
+    // `Symbol` is a function, it does not downcast to `JsObject`
+    let symbol = cx.global::<JsFunction>("Symbol")?;
+    symbol.get(cx, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Items;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Counts its `next` calls and drops, yields `None` once after `len`
+    /// items and then starts over like a non-fused iterator
+    struct Counted {
+        len: u32,
+        pos: u32,
+        calls: Rc<Cell<u32>>,
+        dropped: Rc<Cell<bool>>,
+    }
+
+    impl Counted {
+        fn new(len: u32) -> (Self, Rc<Cell<u32>>, Rc<Cell<bool>>) {
+            let (calls, dropped) = (Rc::default(), Rc::default());
+            let iter = Counted {
+                len,
+                pos: 0,
+                calls: Rc::clone(&calls),
+                dropped: Rc::clone(&dropped),
+            };
+            (iter, calls, dropped)
+        }
+    }
+
+    impl Iterator for Counted {
+        type Item = u32;
+
+        fn next(&mut self) -> Option<u32> {
+            self.calls.set(self.calls.get() + 1);
+            if self.pos == self.len {
+                self.pos = 0;
+                return None;
+            }
+            self.pos += 1;
+            Some(self.pos)
+        }
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.dropped.set(true);
+        }
+    }
+
+    #[test]
+    fn test_next_until_exhausted() {
+        let (iter, calls, dropped) = Counted::new(2);
+        let mut items = Items::new(iter);
+        assert_eq!(items.next(), Some(1));
+        assert_eq!(items.next(), Some(2));
+        assert!(!dropped.get());
+        assert_eq!(items.next(), None);
+        assert!(dropped.get());
+        // not polled again after it was exhausted
+        assert_eq!(items.next(), None);
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_finish_drops_the_iterator() {
+        let (iter, calls, dropped) = Counted::new(5);
+        let mut items = Items::new(iter);
+        assert_eq!(items.next(), Some(1));
+        items.finish();
+        assert!(dropped.get());
+        assert_eq!(items.next(), None);
+        assert_eq!(calls.get(), 1);
+        // `return()` can be called again
+        items.finish();
+    }
+
+    #[test]
+    fn test_empty_iterator() {
+        let (iter, _, dropped) = Counted::new(0);
+        let mut items = Items::new(iter);
+        assert_eq!(items.next(), None);
+        assert!(dropped.get());
+    }
+}
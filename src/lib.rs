@@ -4,6 +4,14 @@
 #![deny(clippy)]
 #![deny(clippy::pedantic)]
 #![allow(stutter)]
+#![cfg_attr(
+    test,
+    allow(
+        clippy::unused_unit,
+        clippy::semicolon_if_nothing_returned,
+        clippy::unnecessary_wraps
+    )
+)]
 #![recursion_limit = "128"]
 
 //!
@@ -21,10 +29,45 @@
 //! Convert a `Handle<js::JsValue>` to
 //! a type implementing `serde::Deserialize`
 //!
+//! #### `neon_serde::from_value_with`
+//! Like `from_value` but with a `Coercion` setting, `Coercion::Lenient`
+//! accepts loosely typed input such as `"42"` for a `u32` and
+//! `Coercion::Strict` rejects anything that is not exactly the target type
+//!
 //! #### `neon_serde::to_value`
 //! Convert a value implementing `serde::Serialize` to
 //! a `Handle<JsValue>`
 //!
+//! #### `neon_serde::call_with`
+//! Call a `Handle<JsFunction>` with a tuple or struct serialized into its
+//! arguments and deserialize the returned value
+//!
+//! #### `neon_serde::from_promise`
+//! Await a `Handle<JsPromise>` from any thread as a future of the
+//! deserialized value
+//!
+//! #### `neon_serde::Value`
+//! A `Send` tree of JS values, built from a `Serialize` value or
+//! deserialized into one on any thread and converted from and to
+//! handles on the JS thread
+//!
+//! #### `neon_serde::Emitter`
+//! Emit events with serialized payloads to a JS listener or `EventEmitter`
+//! from any thread, with a limit on the events waiting for the JS thread
+//!
+//! #### `neon_serde::to_iterator`
+//! Return a Rust iterator as a JS iterator whose items are serialized as
+//! `next()` is called, `to_async_iterator` does the same for a `Stream` with
+//! the `futures` feature
+//!
+//! #### `neon_serde::executor`
+//! Polls the futures of exported `async fn`s on a shared pool of threads
+//! without an I/O reactor, `set_spawner` runs them on another runtime
+//!
+//! #### `neon_serde::settle`
+//! Resolve a `Deferred` from any thread with a `Result` whose `Ok` is
+//! serialized on the JS thread
+//!
 //!
 //! ## Example
 //!
@@ -32,7 +75,7 @@
 //! # #![allow(dead_code)]
 //! use serde::{Serialize, Deserialize};
 //! use neon_serde4 as neon_serde;
-//! use neon_serde::export;
+//! use neon_serde::{export, JsCallback};
 //!
 //! use neon::prelude::*;
 //!
@@ -52,6 +95,128 @@
 //!     fn return_nothing(name: String) {
 //!         println!("Hello, {name}!");
 //!     }
+//!
+//!     /// An `Err` is thrown as a JS `Error`
+//!     fn parse_number(input: String) -> Result<u32, std::num::ParseIntError> {
+//!         input.parse()
+//!     }
+//!
+//!     fn structured_error(code: String) -> Result<(), AnError> {
+//!         Err(AnError { code })
+//!     }
+//!
+//!     /// `async fn`s return a `Promise`, the future runs on the `executor`
+//!     async fn wait_for(millis: u64) -> u64 {
+//!         millis
+//!     }
+//!
+//!     /// `#[blocking]` runs CPU-bound work on the libuv thread pool
+//!     /// and also returns a `Promise`
+//!     #[blocking]
+//!     fn count_primes(below: u32) -> usize {
+//!         (2..below).filter(|n| (2..*n).all(|d| n % d != 0)).count()
+//!     }
+//!
+//!     /// A leading `&mut FunctionContext` and `Handle` arguments are passed
+//!     /// through as is, a `JsResult` is returned without serializing
+//!     fn call_with<'a>(
+//!         cx: &mut FunctionContext<'a>,
+//!         callback: Handle<'a, JsFunction>,
+//!         value: u32,
+//!     ) -> JsResult<'a, JsValue> {
+//!         let value = cx.number(value);
+//!         callback.call_with(cx).arg(value).apply(cx)
+//!     }
+//!
+//!     /// `JsCallback<Args, Ret>` params root the JS function, `call` passes
+//!     /// the `Args` tuple as its arguments and deserializes the returned value,
+//!     /// `call_blocking` does the same from other threads
+//!     fn map_values(
+//!         cx: &mut FunctionContext,
+//!         values: Vec<f64>,
+//!         map: JsCallback<(f64,), f64>,
+//!     ) -> Result<Vec<f64>, neon_serde::errors::Error> {
+//!         values.into_iter().map(|value| map.call(cx, &(value,))).collect()
+//!     }
+//!
+//!     /// `#[js_name]` sets the exported name
+//!     #[js_name = "sumAll"]
+//!     fn sum_all(values: Vec<f64>) -> f64 {
+//!         values.iter().sum()
+//!     }
+//!
+//!     /// `#[options]` reads the params by name from a single object,
+//!     /// `connect({ host: "localhost" })`, a `= default` is used when the
+//!     /// param is `undefined`
+//!     #[options]
+//!     fn connect(host: String, port: u16 = 80, secure: bool = false) -> String {
+//!         let scheme = if secure { "https" } else { "http" };
+//!         format!("{scheme}://{host}:{port}")
+//!     }
+//!
+//!     /// A last `#[rest]` param collects the remaining arguments,
+//!     /// `log("info", 1, 2, 3)`
+//!     fn log(level: String, #[rest] values: Vec<f64>) {
+//!         println!("[{level}] {values:?}");
+//!     }
+//!
+//!     /// `const` items are serialized once when the module is loaded,
+//!     /// `#[frozen]` deeply freezes the value
+//!     #[frozen]
+//!     const LIMITS: &[(&str, u32)] = &[("connections", 64), ("retries", 3)];
+//!
+//!     const VERSION: &str = env!("CARGO_PKG_VERSION");
+//!
+//!     /// `impl` blocks export a struct as a JS class, `new Counter(1)`,
+//!     /// `fn new` is the constructor, `&self` and `&mut self` methods
+//!     /// go on the prototype and other functions on the class
+//!     impl Counter {
+//!         fn new(start: u32) -> Self {
+//!             Counter { count: start }
+//!         }
+//!
+//!         fn increment(&mut self, by: u32 = 1) -> u32 {
+//!             self.count += by;
+//!             self.count
+//!         }
+//!
+//!         fn get(&self) -> u32 {
+//!             self.count
+//!         }
+//!
+//!         fn zero() -> u32 {
+//!             0
+//!         }
+//!     }
+//!
+//!     /// `mod` blocks are exported as nested objects, `crypto.checksum(...)`
+//!     mod crypto {
+//!         fn checksum(data: Vec<u8>) -> u32 {
+//!             data.iter().map(|b| u32::from(*b)).sum()
+//!         }
+//!
+//!         /// `#[cfg]` also removes the export
+//!         #[cfg(unix)]
+//!         fn unix_only() -> bool {
+//!             true
+//!         }
+//!     }
+//! }
+//!
+//! struct Counter {
+//!     count: u32,
+//! }
+//!
+//! /// Errors that are also `Serialize` have their fields copied onto the thrown `Error`
+//! #[derive(Serialize, Debug)]
+//! struct AnError {
+//!     code: String,
+//! }
+//!
+//! impl std::fmt::Display for AnError {
+//!     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+//!         write!(f, "failed with {}", self.code)
+//!     }
 //! }
 //!
 //! fn deserialize_something(mut cx: FunctionContext) -> JsResult<JsValue> {
@@ -80,19 +245,93 @@
 //!
 //! ```
 //!
+//! ## TypeScript declarations
+//!
+//! `neon_serde::declarations!(crate)` lists the exports of the `export!` block
+//! of a module, render them with `neon_serde::ts::render` to write an
+//! `index.d.ts`, see `ts`
+//!
+//! ## Combining `export!` blocks
+//!
+//! `export!` generates the module's `#[neon::main]`, starting the block with
+//! `#![register]` generates a `pub fn register(&mut ModuleContext)` instead
+//!
+//! ```rust,no_run
+//! use neon::prelude::*;
+//!
+//! mod math {
+//!     use neon_serde4::export;
+//!     use neon::prelude::*;
+//!
+//!     export! {
+//!         #![register]
+//!
+//!         fn add(a: f64, b: f64) -> f64 {
+//!             a + b
+//!         }
+//!     }
+//! }
+//!
+//! mod strings {
+//!     use neon_serde4::export;
+//!     use neon::prelude::*;
+//!
+//!     export! {
+//!         #![register]
+//!
+//!         fn shout(text: String) -> String {
+//!             text.to_uppercase()
+//!         }
+//!     }
+//! }
+//!
+//! fn version(mut cx: FunctionContext) -> JsResult<JsString> {
+//!     Ok(cx.string(env!("CARGO_PKG_VERSION")))
+//! }
+//!
+//! #[neon::main]
+//! fn init(mut cx: ModuleContext) -> NeonResult<()> {
+//!     math::register(&mut cx)?;
+//!     strings::register(&mut cx)?;
+//!     cx.export_function("version", version)?;
+//!     Ok(())
+//! }
+//! ```
+//!
 
+pub mod callback;
 pub mod de;
+pub mod emitter;
 pub mod errors;
+pub mod executor;
+#[doc(hidden)]
+pub mod export;
+pub mod iter;
+pub mod promise;
 pub mod ser;
+pub mod ts;
+pub mod value;
 
 #[cfg(feature = "chrono")]
 pub mod date;
 
 mod macros;
 
+pub use callback::call_with;
+pub use callback::JsCallback;
 pub use de::from_value;
 pub use de::from_value_opt;
+pub use de::from_value_opt_with;
+pub use de::from_value_with;
+pub use de::Coercion;
+pub use emitter::Emitter;
+#[cfg(feature = "futures")]
+pub use iter::to_async_iterator;
+pub use iter::to_iterator;
+pub use promise::from_promise;
+pub use promise::settle;
 pub use ser::to_value;
+pub use value::Value;
 
 #[cfg(test)]
 mod tests {
@@ -2,47 +2,1139 @@
 //! Defines macros for easily exporting functions
 //!
 
+/// Exports functions, constants, classes and namespaces to JS, see the
+/// crate docs for the supported items and attributes
+///
+/// An attribute on an item it does not apply to is a compile error
+///
+/// ```compile_fail
+/// # use neon_serde4::export;
+/// # use neon::prelude::*;
+/// export! {
+///     #[frozen]
+///     fn answer() -> u32 {
+///         42
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use neon_serde4::export;
+/// # use neon::prelude::*;
+/// export! {
+///     #[blocking]
+///     const ANSWER: u32 = 42;
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use neon_serde4::export;
+/// # use neon::prelude::*;
+/// struct Counter;
+///
+/// export! {
+///     impl Counter {
+///         #[js_name = "create"]
+///         fn new() -> Self {
+///             Counter
+///         }
+///     }
+/// }
+/// ```
+///
+/// Other attributes and doc comments are kept on the generated item, calling
+/// a `#[deprecated]` function warns as usual
+///
+/// ```compile_fail
+/// # use neon_serde4::export;
+/// # use neon::prelude::*;
+/// export! {
+///     #[deprecated]
+///     fn answer() -> u32 {
+///         42
+///     }
+/// }
+///
+/// #[deny(deprecated)]
+/// fn check() -> u32 {
+///     answer()
+/// }
+/// ```
 #[macro_export]
 macro_rules! export {
 
-    ($(
-        $(#[$func_meta:meta])*
-        fn $name:ident($( $arg:ident : $atype:ty ),*) $(-> $ret:ty)? $code:block
-    )*) => (
+    // Items are first parsed one at a time into
+    // `{ kind source [attributes] [cfgs] name [js name] [generics] [params] [args] [return type] body }`
+    // where kind is `sync`, `blocking` or `async`, source is `arguments` or
+    // `options` and args are the params normalized to `(cx name _ [])`,
+    // `(handle name type [])`, `(callback name type [])`, `(emitter name type [])`,
+    // `(rest name type [])`
+    // or `(serde name type [default])`,
+    // namespaces become `{ mod [attributes] [cfgs] name [js name] [items] }`,
+    // constants become `{ const [attributes] [cfgs] name [js name] [frozen] [type] [value] }`,
+    // classes become `{ impl [attributes] [cfgs] name [js name] [members] }`,
+    // then all items are emitted together, either with their own
+    // `#[neon::main]` or as a `register` function
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($kind:ident)?] [$($source:ident)?] [$($js:literal)?]
+        #[blocking] $($rest:tt)*
+    ) => (
+        $crate::export!(@parse [$($done)*] [$($meta)*] [$($cfg)*] [blocking] [$($source)?] [$($js)?] $($rest)*);
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($kind:ident)?] [$($source:ident)?] [$($js:literal)?]
+        #[options] $($rest:tt)*
+    ) => (
+        $crate::export!(@parse [$($done)*] [$($meta)*] [$($cfg)*] [$($kind)?] [options] [$($js)?] $($rest)*);
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($kind:ident)?] [$($source:ident)?] [$($js:literal)?]
+        #[frozen] $($rest:tt)*
+    ) => (
+        $crate::export!(@parse [$($done)*] [$($meta)*] [$($cfg)*] [frozen] [$($source)?] [$($js)?] $($rest)*);
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($kind:ident)?] [$($source:ident)?] [$($js:literal)?]
+        #[js_name = $js_name:literal] $($rest:tt)*
+    ) => (
+        $crate::export!(@parse [$($done)*] [$($meta)*] [$($cfg)*] [$($kind)?] [$($source)?] [$js_name] $($rest)*);
+    );
+
+    // `#[cfg]` also applies to the export of the item
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($kind:ident)?] [$($source:ident)?] [$($js:literal)?]
+        #[cfg $predicate:tt] $($rest:tt)*
+    ) => (
+        $crate::export!(@parse [$($done)*] [$($meta)*] [$($cfg)* $predicate] [$($kind)?] [$($source)?] [$($js)?] $($rest)*);
+    );
+
+    // doc comments are kept as literals for the TypeScript declarations
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($kind:ident)?] [$($source:ident)?] [$($js:literal)?]
+        #[doc = $doc:literal] $($rest:tt)*
+    ) => (
+        $crate::export!(@parse [$($done)*] [$($meta)* #[doc = $doc]] [$($cfg)*] [$($kind)?] [$($source)?] [$($js)?] $($rest)*);
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($kind:ident)?] [$($source:ident)?] [$($js:literal)?]
+        #[$func_meta:meta] $($rest:tt)*
+    ) => (
+        $crate::export!(@parse [$($done)*] [$($meta)* #[$func_meta]] [$($cfg)*] [$($kind)?] [$($source)?] [$($js)?] $($rest)*);
+    );
+
+    // `#[frozen]` and `#[blocking]` share the kind slot, misplaced ones are
+    // reported here instead of failing to match any rule
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [frozen] [$($source:ident)?] [$($js:literal)?]
+        $(async)? fn $($rest:tt)*
+    ) => (
+        compile_error!("`#[frozen]` only applies to `const` items, not functions");
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [blocking] [$($source:ident)?] [$($js:literal)?]
+        const $($rest:tt)*
+    ) => (
+        compile_error!("`#[blocking]` only applies to functions, not `const` items");
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [blocking] [$($source:ident)?] [$($js:literal)?]
+        async fn $($rest:tt)*
+    ) => (
+        compile_error!("`#[blocking]` can not be combined with `async fn`, which already returns a `Promise`");
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($kind:ident)?] [options] [$($js:literal)?]
+        const $($rest:tt)*
+    ) => (
+        compile_error!("`#[options]` only applies to functions, not `const` items");
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [] [] [$($js:literal)?]
+        mod $name:ident { $($items:tt)* }
+        $($rest:tt)*
+    ) => (
+        $crate::export!(@parse [
+            $($done)*
+            { mod [$($meta)*] [$($cfg)*] $name [$($js)?] [$($items)*] }
+        ] [] [] [] [] [] $($rest)*);
+    );
+
+    // constants are serialized once when the module is loaded
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($frozen:ident)?] [] [$($js:literal)?]
+        const $name:ident : $ctype:ty = $value:expr ;
+        $($rest:tt)*
+    ) => (
+        $crate::export!(@parse [
+            $($done)*
+            { const [$($meta)*] [$($cfg)*] $name [$($js)?] [$($frozen)?] [$ctype] [$value] }
+        ] [] [] [] [] [] $($rest)*);
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [] [] [$($js:literal)?]
+        impl $class:ident { $($members:tt)* }
+        $($rest:tt)*
+    ) => (
+        $crate::export!(@class {
+            [$($done)*] [$($meta)*] [$($cfg)*] $class [$($js)?] [$($rest)*]
+        } [] [] [] [] [] $($members)*);
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [] [$($source:ident)?] [$($js:literal)?]
+        async fn $name:ident $(<$($lt:lifetime),* $(,)?>)? ($($params:tt)*) $(-> $ret:ty)? $code:block
+        $($rest:tt)*
+    ) => (
+        $crate::export!(@params {
+            @parsed_fn [$($done)*] [async] [$($source)? arguments] [$($meta)*] [$($cfg)*] $name [$($js)?]
+            [$(<$($lt),*>)?] [$($ret)?] $code [$($rest)*]
+        } [] [] $($params)* ,);
+    );
+
+    (@parse [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($kind:ident)?] [$($source:ident)?] [$($js:literal)?]
+        fn $name:ident $(<$($lt:lifetime),* $(,)?>)? ($($params:tt)*) $(-> $ret:ty)? $code:block
+        $($rest:tt)*
+    ) => (
+        $crate::export!(@params {
+            @parsed_fn [$($done)*] [$($kind)? sync] [$($source)? arguments] [$($meta)*] [$($cfg)*] $name [$($js)?]
+            [$(<$($lt),*>)?] [$($ret)?] $code [$($rest)*]
+        } [] [] $($params)* ,);
+    );
+
+    (@parse [$($done:tt)*] [] [] [] [] []) => (
+        $crate::export!(@emit $($done)*);
+    );
+
+    (@parsed_fn
+        [$($done:tt)*] [$kind:ident $(sync)?] [$source:ident $(arguments)?] [$($meta:tt)*] [$($cfg:tt)*]
+        $name:ident [$($js:literal)?] [$($generics:tt)*] [$($ret:ty)?] $code:block [$($rest:tt)*]
+        [$($args:tt)*] [$($params:tt)*]
+    ) => (
+        $crate::export!(@parse [
+            $($done)*
+            {
+                $kind $source [$($meta)*] [$($cfg)*] $name [$($js)?]
+                [$($generics)*] [$($params)*] [$($args)*] [$($ret)?] $code
+            }
+        ] [] [] [] [] [] $($rest)*);
+    );
+
+    // The members of an `impl` block are parsed like items into
+    // `{ receiver source [attributes] [cfgs] name [js name] [generics] [params] [args] [return type] body }`
+    // where receiver is `new` for the constructor, `(ref self)` for `&self`,
+    // `(mut self)` for `&mut self` and `static` otherwise
+
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$($js:literal)?]
+        #[options] $($rest:tt)*
+    ) => (
+        $crate::export!(@class $class [$($done)*] [$($meta)*] [$($cfg)*] [options] [$($js)?] $($rest)*);
+    );
+
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$($js:literal)?]
+        #[js_name = $js_name:literal] $($rest:tt)*
+    ) => (
+        $crate::export!(@class $class [$($done)*] [$($meta)*] [$($cfg)*] [$($source)?] [$js_name] $($rest)*);
+    );
+
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$($js:literal)?]
+        #[cfg $predicate:tt] $($rest:tt)*
+    ) => (
+        $crate::export!(@class $class [$($done)*] [$($meta)*] [$($cfg)* $predicate] [$($source)?] [$($js)?] $($rest)*);
+    );
+
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$($js:literal)?]
+        #[doc = $doc:literal] $($rest:tt)*
+    ) => (
+        $crate::export!(@class $class [$($done)*] [$($meta)* #[doc = $doc]] [$($cfg)*] [$($source)?] [$($js)?] $($rest)*);
+    );
+
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$($js:literal)?]
+        #[$member_meta:meta] $($rest:tt)*
+    ) => (
+        $crate::export!(@class $class [$($done)*] [$($meta)* #[$member_meta]] [$($cfg)*] [$($source)?] [$($js)?] $($rest)*);
+    );
+
+    // the constructor is exported as the class, which takes the `#[js_name]`
+    // of the `impl` block
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$js:literal]
+        fn new $($rest:tt)*
+    ) => (
+        compile_error!("`#[js_name]` does not apply to `fn new`, rename the class with `#[js_name]` on its `impl` block");
+    );
+
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$($js:literal)?]
+        fn new $(<$($lt:lifetime),* $(,)?>)? ($($params:tt)*) -> Self $code:block
+        $($rest:tt)*
+    ) => (
+        $crate::export!(@params {
+            @parsed_member $class [$($done)*] [new] [$($source)? arguments] [$($meta)*] [$($cfg)*] new [$($js)?]
+            [$(<$($lt),*>)?] [Self] $code [$($rest)*]
+        } [] [] $($params)* ,);
+    );
+
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$($js:literal)?]
+        fn $name:ident $(<$($lt:lifetime),* $(,)?>)? (&mut $this:ident $(, $($params:tt)*)?) $(-> $ret:ty)? $code:block
+        $($rest:tt)*
+    ) => (
+        $crate::export!(@params {
+            @parsed_member $class [$($done)*] [(mut $this)] [$($source)? arguments] [$($meta)*] [$($cfg)*] $name [$($js)?]
+            [$(<$($lt),*>)?] [$($ret)?] $code [$($rest)*]
+        } [] [] $($($params)*)? ,);
+    );
+
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$($js:literal)?]
+        fn $name:ident $(<$($lt:lifetime),* $(,)?>)? (&$this:ident $(, $($params:tt)*)?) $(-> $ret:ty)? $code:block
+        $($rest:tt)*
+    ) => (
+        $crate::export!(@params {
+            @parsed_member $class [$($done)*] [(ref $this)] [$($source)? arguments] [$($meta)*] [$($cfg)*] $name [$($js)?]
+            [$(<$($lt),*>)?] [$($ret)?] $code [$($rest)*]
+        } [] [] $($($params)*)? ,);
+    );
+
+    (@class $class:tt [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] [$($source:ident)?] [$($js:literal)?]
+        fn $name:ident $(<$($lt:lifetime),* $(,)?>)? ($($params:tt)*) $(-> $ret:ty)? $code:block
+        $($rest:tt)*
+    ) => (
+        $crate::export!(@params {
+            @parsed_member $class [$($done)*] [static] [$($source)? arguments] [$($meta)*] [$($cfg)*] $name [$($js)?]
+            [$(<$($lt),*>)?] [$($ret)?] $code [$($rest)*]
+        } [] [] $($params)* ,);
+    );
+
+    (@class {
+        [$($done:tt)*] [$($meta:tt)*] [$($cfg:tt)*] $class:ident [$($js:literal)?] [$($rest:tt)*]
+    } [$($members:tt)*] [] [] [] []) => (
+        $crate::export!(@parse [
+            $($done)*
+            { impl [$($meta)*] [$($cfg)*] $class [$($js)?] [$($members)*] }
+        ] [] [] [] [] [] $($rest)*);
+    );
+
+    (@parsed_member $class:tt
+        [$($done:tt)*] [$receiver:tt] [$source:ident $(arguments)?] [$($meta:tt)*] [$($cfg:tt)*]
+        $name:ident [$($js:literal)?] [$($generics:tt)*] [$($ret:ty)?] $code:block [$($rest:tt)*]
+        [$($args:tt)*] [$($params:tt)*]
+    ) => (
+        $crate::export!(@class $class [
+            $($done)*
+            {
+                $receiver $source [$($meta)*] [$($cfg)*] $name [$($js)?]
+                [$($generics)*] [$($params)*] [$($args)*] [$($ret)?] $code
+            }
+        ] [] [] [] [] $($rest)*);
+    );
+
+    // Params are munched into args and the params of the Rust function,
+    // which no longer have their default values, then handed back to the
+    // rule in the first group
+
+    // a leading `cx: &mut FunctionContext` receives the context
+    (@params $resume:tt [] [] $cx:ident : &mut FunctionContext $(<$lt:lifetime>)? , $($rest:tt)*) => (
+        $crate::export!(@params $resume
+            [(cx $cx _ [])]
+            [$cx: &mut FunctionContext $(<$lt>)?,]
+            $($rest)*
+        );
+    );
+
+    // `Handle<...>` parameters are passed through without deserializing
+    (@params $resume:tt [$($args:tt)*] [$($clean:tt)*]
+        $arg:ident : Handle<$lt:lifetime, $htype:ty> , $($rest:tt)*
+    ) => (
+        $crate::export!(@params $resume
+            [$($args)* (handle $arg $htype [])]
+            [$($clean)* $arg: Handle<$lt, $htype>,]
+            $($rest)*
+        );
+    );
+
+    (@params $resume:tt [$($args:tt)*] [$($clean:tt)*]
+        $arg:ident : Handle<$htype:ty> , $($rest:tt)*
+    ) => (
+        $crate::export!(@params $resume
+            [$($args)* (handle $arg $htype [])]
+            [$($clean)* $arg: Handle<$htype>,]
+            $($rest)*
+        );
+    );
+
+    // `JsCallback<Args, Ret>` parameters root the JS function
+    (@params $resume:tt [$($args:tt)*] [$($clean:tt)*]
+        $arg:ident : JsCallback<$cargs:ty $(, $cret:ty)?> , $($rest:tt)*
+    ) => (
+        $crate::export!(@params $resume
+            [$($args)* (callback $arg JsCallback<$cargs $(, $cret)?> [])]
+            [$($clean)* $arg: JsCallback<$cargs $(, $cret)?>,]
+            $($rest)*
+        );
+    );
+
+    // `Emitter` parameters root the listener or `EventEmitter`
+    (@params $resume:tt [$($args:tt)*] [$($clean:tt)*]
+        $arg:ident : Emitter , $($rest:tt)*
+    ) => (
+        $crate::export!(@params $resume
+            [$($args)* (emitter $arg Emitter [])]
+            [$($clean)* $arg: Emitter,]
+            $($rest)*
+        );
+    );
+
+    // a last `#[rest] name: Vec<T>` collects the remaining arguments
+    (@params $resume:tt [$($args:tt)*] [$($clean:tt)*]
+        #[rest] $arg:ident : $atype:ty , $(,)?
+    ) => (
+        $crate::export!(@params $resume
+            [$($args)* (rest $arg $atype [])]
+            [$($clean)* $arg: $atype,]
+        );
+    );
+
+    // `name: Type = default` is used when the param is `undefined`
+    (@params $resume:tt [$($args:tt)*] [$($clean:tt)*]
+        $arg:ident : $atype:ty = $default:expr , $($rest:tt)*
+    ) => (
+        $crate::export!(@params $resume
+            [$($args)* (serde $arg $atype [$default])]
+            [$($clean)* $arg: $atype,]
+            $($rest)*
+        );
+    );
+
+    (@params $resume:tt [$($args:tt)*] [$($clean:tt)*]
+        $arg:ident : $atype:ty , $($rest:tt)*
+    ) => (
+        $crate::export!(@params $resume
+            [$($args)* (serde $arg $atype [])]
+            [$($clean)* $arg: $atype,]
+            $($rest)*
+        );
+    );
+
+    (@params { $($resume:tt)* } [$($args:tt)*] [$($params:tt)*] $(,)?) => (
+        $crate::export!($($resume)* [$($args)*] [$($params)*]);
+    );
+
+    (@emit $mode:ident $($item:tt)*) => (
         $(
-            #[allow(non_snake_case)]
-            $(#[$func_meta])*
-            fn $name($( $arg: $atype ),*) $(-> $ret)? $code
+            $crate::export!(@item $item);
         )*
 
+        $crate::export!(@module $mode cx exports {
+            $(
+                $crate::export!(@register cx exports $item);
+            )*
+        });
+
+        $crate::export!(@declarations $mode items {
+            $(
+                $crate::export!(@declare items $item);
+            )*
+        });
+    );
+
+    (@item { mod [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?] [$($items:tt)*] }) => (
+        $($meta)*
+        $(#[cfg $cfg])*
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::export!(@parse [namespace] [] [] [] [] [] $($items)*);
+        }
+    );
+
+    (@item {
+        const [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?] [$($frozen:ident)?] [$ctype:ty] [$value:expr]
+    }) => (
+        $($meta)*
+        $(#[cfg $cfg])*
+        const $name: $ctype = $value;
+    );
+
+    (@item { impl [$($meta:tt)*] [$($cfg:tt)*] $class:ident [$($js:literal)?] [$($member:tt)*] }) => (
+        $($meta)*
+        $(#[cfg $cfg])*
+        impl $class {
+            $(
+                $crate::export!(@member $member);
+            )*
+        }
+    );
+
+    (@item {
+        $kind:ident $source:ident [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?]
+        [$($generics:tt)*] [$($params:tt)*] [$($args:tt)*] [$($ret:ty)?] $code:block
+    }) => (
+        $crate::export!(@define $kind [$($meta)*] [$($cfg)*] $name [$($generics)*] [$($params)*] [$($ret)?] $code);
+    );
+
+    (@member {
+        $receiver:tt $source:ident [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?]
+        [$($generics:tt)*] [$($params:tt)*] [$($args:tt)*] [$($ret:ty)?] $code:block
+    }) => (
+        $crate::export!(@define_member $receiver [$($meta)*] [$($cfg)*] $name [$($generics)*] [$($params)*] [$($ret)?] $code);
+    );
+
+    // a namespace is exported as an object holding its own items
+    (@register $cx:ident $exports:ident {
+        mod [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?] [$($items:tt)*]
+    }) => (
+        $(#[cfg $cfg])*
+        {
+            let namespace = $cx.empty_object();
+            $name::register($cx, namespace)?;
+            $exports.set($cx, $crate::export!(@js_name $name $($js)?), namespace)?;
+        }
+    );
+
+    (@register $cx:ident $exports:ident {
+        const [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?] [$($frozen:ident)?] [$ctype:ty] [$value:expr]
+    }) => (
+        $(#[cfg $cfg])*
+        $crate::export::constant(
+            $cx,
+            $exports,
+            $crate::export!(@js_name $name $($js)?),
+            &$name,
+            $crate::export!(@frozen $($frozen)?),
+        )?;
+    );
+
+    // a class is exported as its constructor, methods are set on its
+    // `prototype` and static functions on the constructor itself
+    (@register $cx:ident $exports:ident {
+        impl [$($meta:tt)*] [$($cfg:tt)*] $class:ident [$($js:literal)?] [$($member:tt)*]
+    }) => (
+        $(#[cfg $cfg])*
+        {
+            let name = $crate::export!(@js_name $class $($js)?);
+            let class = neon::types::JsFunction::with_name(
+                $cx,
+                name,
+                $crate::export!(@constructor $class [$($member)*]),
+            )?;
+            let prototype: neon::handle::Handle<neon::types::JsObject> = class.get($cx, "prototype")?;
+            $(
+                $crate::export!(@register_member $cx class prototype $class $member);
+            )*
+            $exports.set($cx, name, class)?;
+        }
+    );
+
+    (@register $cx:ident $exports:ident {
+        $kind:ident $source:ident [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?]
+        [$($generics:tt)*] [$($params:tt)*] [$($args:tt)*] [$($ret:ty)?] $code:block
+    }) => (
+        $(#[cfg $cfg])*
+        {
+            let name = $crate::export!(@js_name $name $($js)?);
+            let function = neon::types::JsFunction::with_name(
+                $cx,
+                name,
+                $crate::export!(@function $kind $source [stringify!($name)] [$name] [$($args)*] [$($ret)?]),
+            )?;
+            $exports.set($cx, name, function)?;
+        }
+    );
+
+    (@register_member $cx:ident $class:ident $prototype:ident $class_name:ident { new $($member:tt)* }) => ();
+
+    (@register_member $cx:ident $class:ident $prototype:ident $class_name:ident {
+        $receiver:tt $source:ident [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?]
+        [$($generics:tt)*] [$($params:tt)*] [$($args:tt)*] [$($ret:ty)?] $code:block
+    }) => (
+        $(#[cfg $cfg])*
+        {
+            let name = $crate::export!(@js_name $name $($js)?);
+            let function = neon::types::JsFunction::with_name(
+                $cx,
+                name,
+                $crate::export!(@method $receiver $source $class_name $name [$($args)*] [$($ret)?]),
+            )?;
+            $crate::export!(@member_target $receiver $class $prototype).set($cx, name, function)?;
+        }
+    );
+
+    (@member_target static $class:ident $prototype:ident) => ( $class );
+
+    (@member_target $receiver:tt $class:ident $prototype:ident) => ( $prototype );
+
+    (@frozen) => ( false );
+
+    (@frozen frozen) => ( true );
+
+    (@js_name $name:ident) => ( stringify!($name) );
+
+    (@js_name $name:ident $js:literal) => ( $js );
+
+    // exporting a `#[deprecated]` item is not a use of it, only calls from
+    // Rust warn
+    (@module main $cx:ident $exports:ident { $($register:tt)* }) => (
         #[neon::main]
-        pub fn main(mut m: ModuleContext) -> Result<(), neon::result::Throw> {
+        #[allow(deprecated)]
+        pub fn main(mut module: neon::context::ModuleContext) -> neon::result::NeonResult<()> {
+            use neon::context::Context as _;
+            use neon::object::Object as _;
+
+            let $cx = &mut module;
+            let $exports = $cx.exports_object()?;
+            $($register)*
+            Ok(())
+        }
+    );
+
+    (@module register $cx:ident $exports:ident { $($register:tt)* }) => (
+        /// Exports the items of this `export!` block on the module
+        #[allow(deprecated)]
+        pub fn register($cx: &mut neon::context::ModuleContext) -> neon::result::NeonResult<()> {
+            use neon::context::Context as _;
+            use neon::object::Object as _;
+
+            let $exports = $cx.exports_object()?;
+            $($register)*
+            Ok(())
+        }
+    );
+
+    (@module namespace $cx:ident $exports:ident { $($register:tt)* }) => (
+        #[allow(deprecated)]
+        pub(super) fn register<'a, C: neon::context::Context<'a>>(
+            $cx: &mut C,
+            $exports: neon::handle::Handle<'a, neon::types::JsObject>,
+        ) -> neon::result::NeonResult<()> {
+            use neon::object::Object as _;
+
+            $($register)*
+            Ok(())
+        }
+    );
+
+    // `declarations!` lists the items of the block for `ts::render`, the
+    // function is hidden so it does not clash with the user's items
+    (@declarations namespace $items:ident { $($declare:tt)* }) => (
+        #[doc(hidden)]
+        #[allow(deprecated)]
+        pub(super) fn __neon_serde_declarations() -> Vec<$crate::ts::Item> {
+            #[allow(unused_mut)]
+            let mut $items = Vec::new();
+            $($declare)*
+            $items
+        }
+    );
+
+    (@declarations $mode:ident $items:ident { $($declare:tt)* }) => (
+        #[doc(hidden)]
+        #[allow(deprecated)]
+        pub fn __neon_serde_declarations() -> Vec<$crate::ts::Item> {
+            #[allow(unused_mut)]
+            let mut $items = Vec::new();
+            $($declare)*
+            $items
+        }
+    );
+
+    (@declare $items:ident {
+        mod [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?] [$($item:tt)*]
+    }) => (
+        $(#[cfg $cfg])*
+        $items.push($crate::ts::Item::Namespace($crate::ts::Namespace {
+            name: $crate::export!(@js_name $name $($js)?).into(),
+            docs: $crate::export!(@docs [] $($meta)*),
+            items: $name::__neon_serde_declarations(),
+        }));
+    );
+
+    (@declare $items:ident {
+        const [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?] [$($frozen:ident)?] [$ctype:ty] [$value:expr]
+    }) => (
+        $(#[cfg $cfg])*
+        $items.push($crate::ts::Item::Const($crate::ts::Const {
+            name: $crate::export!(@js_name $name $($js)?).into(),
+            docs: $crate::export!(@docs [] $($meta)*),
+            ty: $crate::export!(@describe_value $ctype, &$name),
+        }));
+    );
+
+    (@declare $items:ident {
+        impl [$($meta:tt)*] [$($cfg:tt)*] $class:ident [$($js:literal)?] [$($member:tt)*]
+    }) => (
+        $(#[cfg $cfg])*
+        {
+            let mut class = $crate::ts::Class {
+                name: $crate::export!(@js_name $class $($js)?).into(),
+                docs: $crate::export!(@docs [] $($meta)*),
+                constructor: None,
+                methods: Vec::new(),
+                statics: Vec::new(),
+            };
             $(
-                m.export_function(stringify!($name), |mut cx| {
-                    use neon::result::ResultExt;
+                $crate::export!(@declare_member class $member);
+            )*
+            $items.push($crate::ts::Item::Class(class));
+        }
+    );
+
+    (@declare $items:ident {
+        $kind:ident $source:ident [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?]
+        [$($generics:tt)*] [$($params:tt)*] [$($args:tt)*] [$($ret:ty)?] $code:block
+    }) => (
+        $(#[cfg $cfg])*
+        $items.push($crate::ts::Item::Function(
+            $crate::export!(@declare_function $kind $source [$($meta)*] $name [$($js)?] [$($generics)*] [$($args)*] [$($ret)?])
+        ));
+    );
+
+    (@declare_member $class:ident {
+        new $source:ident [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?]
+        [$($generics:tt)*] [$($params:tt)*] [$($args:tt)*] [$($ret:ty)?] $code:block
+    }) => (
+        $(#[cfg $cfg])*
+        {
+            $class.constructor = Some(
+                $crate::export!(@declare_function sync $source [$($meta)*] $name [] [$($generics)*] [$($args)*] [])
+            );
+        }
+    );
+
+    (@declare_member $class:ident {
+        $receiver:tt $source:ident [$($meta:tt)*] [$($cfg:tt)*] $name:ident [$($js:literal)?]
+        [$($generics:tt)*] [$($params:tt)*] [$($args:tt)*] [$($ret:ty)?] $code:block
+    }) => (
+        $(#[cfg $cfg])*
+        $crate::export!(@member_list $receiver $class).push(
+            $crate::export!(@declare_function sync $source [$($meta)*] $name [$($js)?] [$($generics)*] [$($args)*] [$($ret)?])
+        );
+    );
+
+    (@member_list static $class:ident) => ( $class.statics );
+
+    (@member_list $receiver:tt $class:ident) => ( $class.methods );
+
+    // types are described inside a function declaring the same lifetimes
+    (@declare_function $kind:ident $source:ident [$($meta:tt)*] $name:ident [$($js:literal)?]
+        [$($generics:tt)*] [$($arg:tt)*] [$($ret:ty)?]
+    ) => ({
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn declare $($generics)* () -> $crate::ts::Function {
+            let declared: Vec<Option<$crate::ts::Param>> =
+                vec![$( $crate::export!(@declare_param $arg) ),*];
+            let declared = declared.into_iter().flatten().collect::<Vec<_>>();
+            $crate::ts::Function {
+                name: $crate::export!(@js_name $name $($js)?).into(),
+                docs: $crate::export!(@docs [] $($meta)*),
+                params: $crate::export!(@declare_source $source declared),
+                ret: $crate::export!(@declare_ret $kind $($ret)?),
+            }
+        }
+        declare()
+    });
+
+    (@declare_source arguments $params:ident) => ( $params );
+
+    (@declare_source options $params:ident) => ( $crate::ts::options(&$params) );
+
+    (@declare_param (cx $($arg:tt)*)) => ( None );
+
+    (@declare_param (rest $arg:ident $atype:ty [])) => (
+        Some($crate::ts::Param {
+            name: stringify!($arg).into(),
+            ty: $crate::export!(@describe ts_type $atype),
+            optional: false,
+            rest: true,
+        })
+    );
+
+    (@declare_param (serde $arg:ident $atype:ty [$default:expr])) => (
+        Some($crate::ts::Param {
+            name: stringify!($arg).into(),
+            ty: $crate::export!(@describe ts_type $atype),
+            optional: true,
+            rest: false,
+        })
+    );
+
+    (@declare_param ($akind:ident $arg:ident $atype:ty [])) => (
+        Some($crate::ts::Param {
+            name: stringify!($arg).into(),
+            ty: $crate::export!(@describe ts_type $atype),
+            optional: $crate::export!(@describe optional $atype),
+            rest: false,
+        })
+    );
+
+    (@declare_ret sync) => ( String::from("void") );
 
-                    // Can be done away with a fancier macro
-                    let mut _arg_index = 0;
+    (@declare_ret sync $ret:ty) => ( $crate::export!(@describe_ret $ret) );
 
-                    $(
-                        let $arg = cx.argument_opt(_arg_index);
-                        let $arg: $atype = $crate::from_value_opt(&mut cx, $arg)
-                            .or_else(|err| err.or_throw(&mut cx))?;
-                        _arg_index += 1;
-                    )*
+    (@declare_ret $kind:ident) => ( String::from("Promise<void>") );
 
-                    let result = $name($( $arg ),*);
+    (@declare_ret $kind:ident $ret:ty) => (
+        format!("Promise<{}>", $crate::export!(@describe_ret $ret))
+    );
+
+    (@describe $method:ident $type:ty) => ({
+        #[allow(unused_imports)]
+        use $crate::ts::{DescribeTraced as _, DescribeTsType as _, DescribeUnknown as _};
+        (&&&&$crate::ts::Describe::<$type>::new()).$method()
+    });
+
+    // a returned type is traced only if it also implements `Serialize`, see
+    // `DescribeReturned`
+    (@describe_ret $type:ty) => ({
+        #[allow(unused_imports)]
+        use $crate::ts::{DescribeReturned as _, DescribeTsType as _, DescribeUnknown as _};
+        (&&&&$crate::ts::Describe::<$type>::new()).ts_type()
+    });
+
+    (@describe_value $type:ty, $value:expr) => ({
+        use $crate::ts::DescribeProduced as _;
+        (&&&&$crate::ts::Describe::<$type>::new()).value_type($value)
+    });
+
+    (@docs [$($doc:literal)*] #[doc = $line:literal] $($rest:tt)*) => (
+        $crate::export!(@docs [$($doc)* $line] $($rest)*)
+    );
+
+    (@docs [$($doc:literal)*] #[$meta:meta] $($rest:tt)*) => (
+        $crate::export!(@docs [$($doc)*] $($rest)*)
+    );
+
+    (@docs [$($doc:literal)*]) => ( vec![$(String::from($doc)),*] );
+
+    // the attributes and docs of the item go on the generated fn
+    (@define async [$($meta:tt)*] [$($cfg:tt)*] $name:ident
+        [$($generics:tt)*] [$($params:tt)*] [$($ret:ty)?] $code:block
+    ) => (
+        #[allow(non_snake_case)]
+        $($meta)*
+        $(#[cfg $cfg])*
+        async fn $name $($generics)* ($($params)*) $(-> $ret)? $code
+    );
+
+    (@define $kind:ident [$($meta:tt)*] [$($cfg:tt)*] $name:ident
+        [$($generics:tt)*] [$($params:tt)*] [$($ret:ty)?] $code:block
+    ) => (
+        #[allow(non_snake_case)]
+        $($meta)*
+        $(#[cfg $cfg])*
+        fn $name $($generics)* ($($params)*) $(-> $ret)? $code
+    );
+
+    (@define_member (ref $this:tt) [$($meta:tt)*] [$($cfg:tt)*] $name:ident
+        [$($generics:tt)*] [$($params:tt)*] [$($ret:ty)?] $code:block
+    ) => (
+        #[allow(non_snake_case)]
+        $($meta)*
+        $(#[cfg $cfg])*
+        fn $name $($generics)* (&$this, $($params)*) $(-> $ret)? $code
+    );
+
+    (@define_member (mut $this:tt) [$($meta:tt)*] [$($cfg:tt)*] $name:ident
+        [$($generics:tt)*] [$($params:tt)*] [$($ret:ty)?] $code:block
+    ) => (
+        #[allow(non_snake_case)]
+        $($meta)*
+        $(#[cfg $cfg])*
+        fn $name $($generics)* (&mut $this, $($params)*) $(-> $ret)? $code
+    );
+
+    (@define_member $receiver:tt [$($meta:tt)*] [$($cfg:tt)*] $name:ident
+        [$($generics:tt)*] [$($params:tt)*] [$($ret:ty)?] $code:block
+    ) => (
+        #[allow(non_snake_case)]
+        $($meta)*
+        $(#[cfg $cfg])*
+        fn $name $($generics)* ($($params)*) $(-> $ret)? $code
+    );
+
+    (@function sync $source:ident [$label:expr] [$($call:tt)*] [$($arg:tt)*] [$($ret:ty)?]) => (
+        |mut cx| {
+            use neon::context::Context as _;
+
+            $crate::export!(@args cx [$label] $source [$($arg)*]);
+
+            // a panic must not unwind across the N-API boundary
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                $($call)*($( $crate::export!(@pass cx $arg) ),*)
+            }));
+            let result = $crate::export!(@unwrap_panic cx [$label] result);
+            let handle = $crate::export!( @handle cx, result, $($ret)? );
+            Ok(handle)
+        }
+    );
+
+    // CPU-bound work runs on the libuv thread pool and resolves a `Promise`
+    (@function blocking $source:ident [$label:expr] [$($call:tt)*] [$($arg:tt)*] [$($ret:ty)?]) => (
+        |mut cx| {
+            use neon::context::Context as _;
+
+            $crate::export!(@args cx [$label] $source [$($arg)*]);
+
+            let promise = cx
+                .task(move || {
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+                        $($call)*($( $crate::export!(@send $arg) ),*)
+                    }))
+                })
+                .promise(move |mut cx, result| {
+                    let result = $crate::export!(@unwrap_panic cx [$label] result);
                     let handle = $crate::export!( @handle cx, result, $($ret)? );
                     Ok(handle)
-                })?;
-            )*
-            Ok(())
+                });
+            Ok(promise.upcast::<neon::types::JsValue>())
         }
     );
 
-    ( @handle $cx:ident, $result:ident, $ret:ty ) => (
-        $crate::to_value(&mut $cx, &$result).or_else(|err| err.or_throw(&mut $cx))?
+    // the future runs on the `executor` and settles a `Promise` through a `Channel`
+    (@function async $source:ident [$label:expr] [$($call:tt)*] [$($arg:tt)*] [$($ret:ty)?]) => (
+        |mut cx| {
+            use neon::context::Context as _;
+
+            $crate::export!(@args cx [$label] $source [$($arg)*]);
+
+            let channel = cx.channel();
+            let (deferred, promise) = cx.promise();
+            let future = $($call)*($( $crate::export!(@send $arg) ),*);
+            $crate::executor::spawn(async move {
+                let result = $crate::export::catch_unwind(future).await;
+                deferred.settle_with(&channel, move |mut cx| {
+                    let result = $crate::export!(@unwrap_panic cx [$label] result);
+                    let handle = $crate::export!( @handle cx, result, $($ret)? );
+                    Ok(handle)
+                });
+            });
+            Ok(promise.upcast::<neon::types::JsValue>())
+        }
     );
 
+    // `new` stores the value in a `JsBox` on the new object
+    (@constructor $class:ident [{ new $source:ident $meta:tt $cfg:tt new $js:tt
+        $generics:tt $params:tt [$($arg:tt)*] $ret:tt $code:block
+    } $($member:tt)*]) => (
+        |mut cx| {
+            use neon::context::Context as _;
+
+            $crate::export::check_construct(&mut cx, stringify!($class))?;
+            $crate::export!(@args cx [stringify!($class)] $source [$($arg)*]);
+
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                $class::new($( $crate::export!(@pass cx $arg) ),*)
+            }));
+            let instance = $crate::export!(@unwrap_panic cx [stringify!($class)] result);
+            $crate::export::construct(&mut cx, instance)?;
+            Ok(cx.undefined())
+        }
+    );
+
+    (@constructor $class:ident [$member:tt $($rest:tt)*]) => (
+        $crate::export!(@constructor $class [$($rest)*])
+    );
+
+    (@constructor $class:ident []) => (
+        |mut cx| -> neon::result::JsResult<neon::types::JsUndefined> {
+            use neon::context::Context as _;
+
+            cx.throw_type_error(concat!("'", stringify!($class), "' has no constructor"))
+        }
+    );
+
+    (@method static $source:ident $class:ident $name:ident [$($arg:tt)*] [$($ret:ty)?]) => (
+        $crate::export!(@function sync $source
+            [concat!(stringify!($class), ".", stringify!($name))]
+            [$class::$name]
+            [$($arg)*]
+            [$($ret)?]
+        )
+    );
+
+    // methods borrow the value behind `this` for the duration of the call
+    (@method ($receiver:ident $this:tt) $source:ident $class:ident $name:ident [$($arg:tt)*] [$($ret:ty)?]) => (
+        |mut cx| {
+            use neon::context::Context as _;
+
+            let label = concat!(stringify!($class), ".", stringify!($name));
+            $crate::export!(@args cx [label] $source [$($arg)*]);
+
+            let instance = $crate::export::this_instance::<$class>(&mut cx, label)?;
+            let result = {
+                #[allow(unused_mut)]
+                let mut instance = $crate::export!(@borrow $receiver)(&instance, &mut cx, label)?;
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    $class::$name(
+                        $crate::export!(@receiver $receiver instance),
+                        $( $crate::export!(@pass cx $arg) ),*
+                    )
+                }))
+            };
+            let result = $crate::export!(@unwrap_panic cx [label] result);
+            let handle = $crate::export!( @handle cx, result, $($ret)? );
+            Ok(handle)
+        }
+    );
+
+    (@borrow ref) => ( $crate::export::Instance::borrow );
+
+    (@borrow mut) => ( $crate::export::Instance::borrow_mut );
+
+    (@receiver ref $instance:ident) => ( &$instance );
+
+    (@receiver mut $instance:ident) => ( &mut $instance );
+
+    // arguments are always read on the JS thread
+    (@args $cx:ident [$label:expr] $source:ident [$($arg:tt)*]) => (
+        #[allow(unused_variables)]
+        let params = $crate::export!(@source $cx [$label] $source);
+
+        #[allow(unused_mut)]
+        let mut _arg_index = 0;
+
+        $(
+            $crate::export!(@arg $cx [$label] params _arg_index $arg);
+        )*
+
+        // a function taking the context may read more arguments itself
+        if !$crate::export!(@takes_cx $($arg)*) {
+            $crate::export!(@arity $cx [$label] $source _arg_index);
+        }
+    );
+
+    (@takes_cx (cx $($arg:tt)*) $($rest:tt)*) => ( true );
+
+    (@takes_cx $arg:tt $($rest:tt)*) => ( $crate::export!(@takes_cx $($rest)*) );
+
+    (@takes_cx) => ( false );
+
+    (@source $cx:ident [$label:expr] arguments) => ( $crate::export::Params::Arguments );
+
+    // `#[options]` reads the params from the properties of a single object
+    (@source $cx:ident [$label:expr] options) => (
+        $crate::export::Params::options(&mut $cx, $label)?
+    );
+
+    (@arity $cx:ident [$label:expr] arguments $count:ident) => (
+        $crate::export::check_arity(&mut $cx, $label, $count)?;
+    );
+
+    (@arity $cx:ident [$label:expr] options $count:ident) => (
+        $crate::export::check_arity(&mut $cx, $label, 1)?;
+    );
+
+    (@arg $cx:ident [$label:expr] $params:ident $index:ident (cx $arg:ident $atype:tt [])) => ();
+
+    (@arg $cx:ident [$label:expr] $params:ident $index:ident (handle $arg:ident $atype:ty [])) => (
+        let $arg = $crate::export::handle_argument::<$atype>(
+            &mut $cx,
+            $params,
+            $label,
+            stringify!($arg),
+            $index,
+        )?;
+        $index += 1;
+    );
+
+    (@arg $cx:ident [$label:expr] $params:ident $index:ident (callback $arg:ident $atype:ty [])) => (
+        let $arg: $atype = $crate::export::callback_argument(
+            &mut $cx,
+            $params,
+            $label,
+            stringify!($arg),
+            $index,
+        )?;
+        $index += 1;
+    );
+
+    (@arg $cx:ident [$label:expr] $params:ident $index:ident (emitter $arg:ident $atype:ty [])) => (
+        let $arg: $atype = $crate::export::emitter_argument(
+            &mut $cx,
+            $params,
+            $label,
+            stringify!($arg),
+            $index,
+        )?;
+        $index += 1;
+    );
+
+    (@arg $cx:ident [$label:expr] $params:ident $index:ident (rest $arg:ident $atype:ty [])) => (
+        let $arg: $atype = $crate::export::rest_arguments(
+            &mut $cx,
+            $params,
+            $label,
+            stringify!($arg),
+            $index,
+        )?;
+        // every argument is taken
+        $index = $index.max($cx.len());
+    );
+
+    (@arg $cx:ident [$label:expr] $params:ident $index:ident
+        (serde $arg:ident $atype:ty [$($default:expr)?])
+    ) => (
+        let $arg: $atype = $crate::export::argument(
+            &mut $cx,
+            $params,
+            $label,
+            stringify!($arg),
+            $index,
+            $crate::export!(@default $($default)?),
+        )?;
+        $index += 1;
+    );
+
+    (@default) => ( None::<fn() -> _> );
+
+    (@default $default:expr) => ( Some(|| $default) );
+
+    (@pass $cx:ident (cx $($arg:tt)*)) => ( &mut $cx );
+
+    (@pass $cx:ident ($akind:ident $arg:ident $($rest:tt)*)) => ( $arg );
+
+    // `async` and `#[blocking]` functions run off the JS thread
+    (@send (cx $($arg:tt)*)) => (
+        compile_error!("only synchronous exported functions can take the FunctionContext")
+    );
+
+    (@send ($akind:ident $arg:ident $($rest:tt)*)) => ( $arg );
+
+    ( @unwrap_panic $cx:ident [$label:expr] $result:ident ) => (
+        match $result {
+            Ok(result) => result,
+            Err(panic) => {
+                return $crate::export::throw_panic(&mut $cx, $label, &*panic)
+            }
+        }
+    );
+
+    ( @handle $cx:ident, $result:ident, $ret:ty ) => ({
+        #[allow(unused_imports)]
+        use $crate::export::{
+            ToJsHandle as _, ToJsLibResult as _, ToJsResult as _, ToJsStructuredResult as _,
+            ToJsValue as _,
+        };
+        (&&&&&$crate::export::Returned::new($result)).to_js(&mut $cx)?
+    });
+
     ( @handle $cx:ident, $result:ident, ) => ( $cx.undefined() );
+
+    // only generates `pub fn register(&mut ModuleContext)`, leaving
+    // `#[neon::main]` to the crate so several blocks can be combined
+    (#![register] $($items:tt)*) => (
+        $crate::export!(@parse [register] [] [] [] [] [] $($items)*);
+    );
+
+    ($($items:tt)*) => (
+        $crate::export!(@parse [main] [] [] [] [] [] $($items)*);
+    );
+}
+
+/// The TypeScript declarations of the `export!` block in the module at
+/// `path`, `declarations!(crate)` or `declarations!(crate::math)`, render
+/// them with `ts::render`
+///
+/// ```rust,ignore
+/// let mut items = neon_serde::declarations!(crate);
+/// items.extend(neon_serde::declarations!(crate::math));
+/// let dts = neon_serde::ts::render(&items);
+/// ```
+#[macro_export]
+macro_rules! declarations {
+    ($($path:ident)::+) => (
+        $($path)::+::__neon_serde_declarations()
+    );
 }
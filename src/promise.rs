@@ -0,0 +1,307 @@
+//!
+//! Await JS promises as Rust futures of deserialized values and settle
+//! promises with serialized ones
+//!
+
+use crate::callback::exception_message;
+use crate::de::{from_value, from_value_opt_with, Coercion};
+use crate::errors::{Error as LibError, Result as LibResult};
+use crate::ser::to_value;
+use crate::value::Value as OwnedValue;
+use neon::event::JoinHandle;
+use neon::prelude::*;
+use neon::result::ResultExt;
+use neon::types::Deferred;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context as TaskContext, Poll, Waker};
+
+/// The reason a promise was rejected with
+///
+/// `reason` keeps the whole payload, deserialize it with `deserialize_reason`
+/// and `or_throw` rethrows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    /// `reason.message` for an `Error`, `String(reason)` otherwise
+    pub message: String,
+    /// `reason.name`, such as `"TypeError"`
+    pub name: Option<String>,
+    /// `reason.code`, numbers are converted to strings
+    pub code: Option<String>,
+    /// The reason read with `Value::from_js`, the `message` and `name` of an
+    /// object are added to its own enumerable properties. A reason that can
+    /// not be read is its `message`
+    pub reason: OwnedValue,
+}
+
+impl Rejection {
+    /// Deserializes the reason into `E`, an `Error` rejection as a struct
+    /// with a `message` field and its own properties
+    ///
+    /// ```rust,no_run
+    /// # use neon_serde4 as neon_serde;
+    /// # use neon_serde::promise::Rejection;
+    /// #[derive(serde::Deserialize)]
+    /// struct HttpError {
+    ///     message: String,
+    ///     status: u16,
+    /// }
+    ///
+    /// # fn status(rejection: &Rejection) -> Option<u16> {
+    /// // `Promise.reject(Object.assign(new Error("not found"), { status: 404 }))`
+    /// let err: HttpError = rejection.deserialize_reason().ok()?;
+    /// # Some(err.status)
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// * any error of `Value::deserialize_into`
+    pub fn deserialize_reason<E: DeserializeOwned>(&self) -> LibResult<E> {
+        self.reason.clone().deserialize_into()
+    }
+
+    /// Reads the reason on the JS thread, an exception thrown while reading
+    /// it is left for the caller
+    fn read<'j, C: Context<'j>>(cx: &mut C, reason: Handle<'j, JsValue>) -> LibResult<Self> {
+        let Ok(object) = reason.downcast::<JsObject, _>(cx) else {
+            let message = reason.to_string(cx)?.value(cx);
+            return Ok(Rejection {
+                reason: read_reason(cx, reason, &message)?,
+                message,
+                name: None,
+                code: None,
+            });
+        };
+        let message = object.get_value(cx, "message")?;
+        let own_message: Option<String> =
+            from_value_opt_with(cx, Some(message), Coercion::Lenient)?;
+        let message = match &own_message {
+            Some(message) => message.clone(),
+            None => reason.to_string(cx)?.value(cx),
+        };
+        let name = object.get_value(cx, "name")?;
+        let name: Option<String> = from_value_opt_with(cx, Some(name), Coercion::Lenient)?;
+        let code = object.get_value(cx, "code")?;
+
+        let mut value = read_reason(cx, reason, &message)?;
+        if let OwnedValue::Object(properties) = &mut value {
+            // not enumerable on an `Error`
+            if let Some(name) = &name {
+                insert_missing(properties, "name", name);
+            }
+            if let Some(message) = &own_message {
+                insert_missing(properties, "message", message);
+            }
+        }
+        Ok(Rejection {
+            message,
+            name,
+            code: from_value_opt_with(cx, Some(code), Coercion::Lenient)?,
+            reason: value,
+        })
+    }
+
+    /// Builds the reason on the JS thread again, an object with a `message`
+    /// becomes an `Error` with the other properties set on it
+    pub(crate) fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> LibResult<Handle<'j, JsValue>> {
+        let OwnedValue::Object(properties) = &self.reason else {
+            return self.reason.to_js(cx);
+        };
+        if !properties.iter().any(|(key, _)| key == "message") {
+            return self.reason.to_js(cx);
+        }
+        let error = cx.error(&self.message)?;
+        for (key, property) in properties {
+            if key != "message" {
+                let property = property.to_js(cx)?;
+                error.set(cx, key.as_str(), property)?;
+            }
+        }
+        Ok(error.upcast())
+    }
+}
+
+/// Inserts the string property `key` first unless the object has it
+fn insert_missing(properties: &mut Vec<(String, OwnedValue)>, key: &str, value: &str) {
+    if !properties.iter().any(|(k, _)| k == key) {
+        properties.insert(0, (key.into(), OwnedValue::String(value.into())));
+    }
+}
+
+/// Reads the reason as a `Value`, falling back to its message
+fn read_reason<'j, C: Context<'j>>(
+    cx: &mut C,
+    reason: Handle<'j, JsValue>,
+    message: &str,
+) -> LibResult<OwnedValue> {
+    match OwnedValue::from_js(cx, reason) {
+        Err(LibError::Js { throw }) => Err(LibError::Js { throw }),
+        Err(_) => Ok(OwnedValue::String(message.into())),
+        value => value,
+    }
+}
+
+/// How the promise settled, only `Send` values leave the JS thread
+enum Settled<T> {
+    Fulfilled(T),
+    Rejected(Rejection),
+    Failed(String),
+}
+
+struct State<T> {
+    settled: Option<Settled<T>>,
+    waker: Option<Waker>,
+}
+
+/// A future of the value a JS promise is fulfilled with, see `from_promise`
+pub struct PromiseFuture<T> {
+    state: Arc<Mutex<State<T>>>,
+}
+
+impl<T> Future for PromiseFuture<T> {
+    type Output = LibResult<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<LibResult<T>> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        match state.settled.take() {
+            Some(Settled::Fulfilled(value)) => Poll::Ready(Ok(value)),
+            Some(Settled::Rejected(reason)) => Poll::Ready(Err(LibError::Rejected { reason })),
+            Some(Settled::Failed(msg)) => Poll::Ready(Err(LibError::Deserialize { msg })),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Awaits `promise` from any thread, the fulfilled value is deserialized
+/// into `T` on the JS thread
+///
+/// A rejection is returned as `Error::Rejected` with the reason as a
+/// `Rejection`, a value that can not be deserialized as `Error::Deserialize`.
+/// The future is pending until the promise settles
+///
+/// ```rust,no_run
+/// # use neon_serde4 as neon_serde;
+/// use neon::prelude::*;
+/// use neon::result::ResultExt;
+///
+/// # fn spawn(_: impl std::future::Future<Output = ()> + Send + 'static) {}
+/// fn on_ready(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+///     let promise = cx.argument::<JsPromise>(0)?;
+///     let names = neon_serde::from_promise::<_, Vec<String>>(&mut cx, promise)
+///         .or_else(|err| err.or_throw(&mut cx))?;
+///     spawn(async move {
+///         match names.await {
+///             Ok(names) => println!("ready: {names:?}"),
+///             Err(err) => eprintln!("{err}"),
+///         }
+///     });
+///     Ok(cx.undefined())
+/// }
+/// ```
+///
+/// # Errors
+///
+/// * `Js` if calling `promise.then` threw
+pub fn from_promise<'j, C, T>(
+    cx: &mut C,
+    promise: Handle<'j, JsPromise>,
+) -> LibResult<PromiseFuture<T>>
+where
+    C: Context<'j>,
+    T: DeserializeOwned + Send + 'static,
+{
+    let state = Arc::new(Mutex::new(State {
+        settled: None,
+        waker: None,
+    }));
+
+    let fulfilled = handler(cx, &state, |cx, value| {
+        Ok(Settled::Fulfilled(from_value(cx, value)?))
+    })?;
+    let rejected = handler(cx, &state, |cx, reason| {
+        Ok(Settled::Rejected(Rejection::read(cx, reason)?))
+    })?;
+
+    let then: Handle<JsFunction> = promise.get(cx, "then")?;
+    then.call(cx, promise, [fulfilled.upcast(), rejected.upcast()])?;
+    Ok(PromiseFuture { state })
+}
+
+/// A `then` handler storing what `settle` makes of its argument and waking
+/// the future, exceptions are caught so the handler never throws
+fn handler<'j, C, T, F>(
+    cx: &mut C,
+    state: &Arc<Mutex<State<T>>>,
+    settle: F,
+) -> JsResult<'j, JsFunction>
+where
+    C: Context<'j>,
+    T: Send + 'static,
+    F: for<'a> Fn(&mut FunctionContext<'a>, Handle<'a, JsValue>) -> LibResult<Settled<T>> + 'static,
+{
+    let state = Arc::clone(state);
+    JsFunction::new(cx, move |mut cx| {
+        let value = cx.argument::<JsValue>(0)?;
+        let settled = cx
+            .try_catch(|cx| match settle(cx, value) {
+                Ok(settled) => Ok(Ok(settled)),
+                Err(LibError::Js { throw }) => Err(throw),
+                Err(LibError::Deserialize { msg }) => Ok(Err(msg)),
+                Err(err) => Ok(Err(err.to_string())),
+            })
+            .unwrap_or_else(|exception| Err(exception_message(&mut cx, exception)))
+            .unwrap_or_else(Settled::Failed);
+
+        let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.settled = Some(settled);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        Ok(cx.undefined())
+    })
+}
+
+/// Settles the promise of `deferred` from any thread, `Ok` is serialized
+/// with `to_value` on the JS thread and the promise is fulfilled with it
+///
+/// An `Err` rejects the promise with an `Error` carrying its `Display`
+/// message, a value that can not be serialized with a `TypeError`
+///
+/// ```rust,no_run
+/// # use neon_serde4 as neon_serde;
+/// use neon::prelude::*;
+///
+/// fn read_config(mut cx: FunctionContext) -> JsResult<JsPromise> {
+///     let path = cx.argument::<JsString>(0)?.value(&mut cx);
+///     let channel = cx.channel();
+///     let (deferred, promise) = cx.promise();
+///     std::thread::spawn(move || {
+///         let lines = std::fs::read_to_string(path)
+///             .map(|config| config.lines().map(String::from).collect::<Vec<_>>());
+///         neon_serde::settle(&channel, deferred, lines);
+///     });
+///     Ok(promise)
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Like `Deferred::settle_with`, if the closure can not be sent to the JS thread
+pub fn settle<T, E>(channel: &Channel, deferred: Deferred, result: Result<T, E>) -> JoinHandle<()>
+where
+    T: Serialize + Send + 'static,
+    E: Display + Send + 'static,
+{
+    deferred.settle_with(channel, move |mut cx| match result {
+        Ok(value) => to_value(&mut cx, &value).or_else(|err| err.or_throw(&mut cx)),
+        Err(err) => cx.throw_error(err.to_string()),
+    })
+}
@@ -21,6 +21,7 @@ fn as_num<T: num::cast::NumCast, OutT: num::cast::NumCast>(n: T) -> LibResult<Ou
 ///
 /// * `NumberCastError` trying to serialize a `u64` can fail if it overflows in a cast to `f64`
 /// * `StringTooLong` if the string exceeds v8's max string size
+/// * `UnsupportedMapKey` if a map key is not a string, number, bool, char or unit variant
 ///
 #[inline]
 pub fn to_value<'j, C, V>(cx: &mut C, value: &V) -> LibResult<Handle<'j, JsValue>>
@@ -70,9 +71,25 @@ where
 {
     cx: &'a mut C,
     object: Handle<'j, JsObject>,
-    key_holder: Handle<'j, JsObject>,
+    key: Option<Handle<'j, JsValue>>,
 }
 
+/// A map key usable as a property name
+#[doc(hidden)]
+#[derive(Debug, PartialEq)]
+pub enum MapKey {
+    Name(String),
+    /// Floats are left for JS to stringify, `1.0` becomes `"1"`
+    Number(f64),
+}
+
+/// Serializes map keys to a `MapKey`
+///
+/// Integers are formatted in Rust so that values above `2^53` keep all of their
+/// digits, composite keys are rejected
+#[doc(hidden)]
+pub struct MapKeySerializer;
+
 #[doc(hidden)]
 pub struct StructSerializer<'a, 'j, C: 'a>
 where
@@ -200,9 +217,9 @@ where
     }
 
     #[inline]
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
@@ -228,13 +245,13 @@ where
     }
 
     #[inline]
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         #[allow(unused)] name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         #[cfg(feature = "chrono")]
         if name == crate::date::CHRONO_DATE_TIME_INTERNAL_NAME {
@@ -244,7 +261,7 @@ where
     }
 
     #[inline]
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
@@ -252,7 +269,7 @@ where
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let obj = JsObject::new(&mut *self.cx);
         let value_js = to_value(self.cx, value)?;
@@ -330,16 +347,16 @@ where
 }
 
 #[doc(hidden)]
-impl<'a, 'j, C> ser::SerializeSeq for ArraySerializer<'a, 'j, C>
+impl<'j, C> ser::SerializeSeq for ArraySerializer<'_, 'j, C>
 where
     C: Context<'j>,
 {
     type Ok = Handle<'j, JsValue>;
     type Error = LibError;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let value = to_value(self.cx, value)?;
 
@@ -355,7 +372,7 @@ where
     }
 }
 
-impl<'a, 'j, C> ser::SerializeTuple for ArraySerializer<'a, 'j, C>
+impl<'j, C> ser::SerializeTuple for ArraySerializer<'_, 'j, C>
 where
     C: Context<'j>,
 {
@@ -363,9 +380,9 @@ where
     type Error = LibError;
 
     #[inline]
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         ser::SerializeSeq::serialize_element(self, value)
     }
@@ -377,7 +394,7 @@ where
 }
 
 #[doc(hidden)]
-impl<'a, 'j, C> ser::SerializeTupleStruct for ArraySerializer<'a, 'j, C>
+impl<'j, C> ser::SerializeTupleStruct for ArraySerializer<'_, 'j, C>
 where
     C: Context<'j>,
 {
@@ -385,9 +402,9 @@ where
     type Error = LibError;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         ser::SerializeSeq::serialize_element(self, value)
     }
@@ -418,7 +435,7 @@ where
 }
 
 #[doc(hidden)]
-impl<'a, 'j, C> ser::SerializeTupleVariant for TupleVariantSerializer<'a, 'j, C>
+impl<'j, C> ser::SerializeTupleVariant for TupleVariantSerializer<'_, 'j, C>
 where
     C: Context<'j>,
 {
@@ -426,9 +443,9 @@ where
     type Error = LibError;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         use serde::ser::SerializeSeq;
         self.inner.serialize_element(value)
@@ -447,37 +464,46 @@ where
 {
     fn new(cx: &'a mut C) -> Self {
         let object = JsObject::new(cx);
-        let key_holder = JsObject::new(cx);
         MapSerializer {
             cx,
             object,
-            key_holder,
+            key: None,
         }
     }
 }
 
 #[doc(hidden)]
-impl<'a, 'j, C> ser::SerializeMap for MapSerializer<'a, 'j, C>
+impl<'j, C> ser::SerializeMap for MapSerializer<'_, 'j, C>
 where
     C: Context<'j>,
 {
     type Ok = Handle<'j, JsValue>;
     type Error = LibError;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        let key = to_value(self.cx, key)?;
-        self.key_holder.set(self.cx, "key", key)?;
+        let key = match key.serialize(MapKeySerializer)? {
+            MapKey::Name(name) => {
+                let len = name.len();
+                JsString::try_new(self.cx, name)
+                    .map_err(|_| LibError::StringTooLong { len })?
+                    .upcast()
+            }
+            MapKey::Number(v) => JsNumber::new(self.cx, v).upcast(),
+        };
+        self.key = Some(key);
         Ok(())
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        let key: Handle<'j, JsValue> = self.key_holder.get(&mut *self.cx, "key")?;
+        let key = self.key.take().ok_or_else(|| {
+            <LibError as ser::Error>::custom("map value serialized before its key")
+        })?;
         let value_obj = to_value(self.cx, value)?;
         self.object.set(self.cx, key, value_obj)?;
         Ok(())
@@ -489,6 +515,194 @@ where
     }
 }
 
+#[doc(hidden)]
+impl ser::Serializer for MapKeySerializer {
+    type Ok = MapKey;
+    type Error = LibError;
+
+    type SerializeSeq = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTuple = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleStruct = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeTupleVariant = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeStruct = ser::Impossible<Self::Ok, Self::Error>;
+    type SerializeStructVariant = ser::Impossible<Self::Ok, Self::Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Number(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Err(LibError::UnsupportedMapKey { key_type: "bytes" })
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Err(LibError::UnsupportedMapKey { key_type: "option" })
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(LibError::UnsupportedMapKey { key_type: "option" })
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(LibError::UnsupportedMapKey { key_type: "unit" })
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Err(LibError::UnsupportedMapKey {
+            key_type: "unit struct",
+        })
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(MapKey::Name(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(LibError::UnsupportedMapKey {
+            key_type: "newtype variant",
+        })
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(LibError::UnsupportedMapKey { key_type: "seq" })
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(LibError::UnsupportedMapKey { key_type: "tuple" })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(LibError::UnsupportedMapKey {
+            key_type: "tuple struct",
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(LibError::UnsupportedMapKey {
+            key_type: "tuple variant",
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(LibError::UnsupportedMapKey { key_type: "map" })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(LibError::UnsupportedMapKey { key_type: "struct" })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(LibError::UnsupportedMapKey {
+            key_type: "struct variant",
+        })
+    }
+}
+
 #[doc(hidden)]
 impl<'a, 'j, C> StructSerializer<'a, 'j, C>
 where
@@ -502,7 +716,7 @@ where
 }
 
 #[doc(hidden)]
-impl<'a, 'j, C> ser::SerializeStruct for StructSerializer<'a, 'j, C>
+impl<'j, C> ser::SerializeStruct for StructSerializer<'_, 'j, C>
 where
     C: Context<'j>,
 {
@@ -510,13 +724,9 @@ where
     type Error = LibError;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         let value = to_value(self.cx, value)?;
         self.object.set(self.cx, key, value)?;
@@ -549,7 +759,7 @@ where
 }
 
 #[doc(hidden)]
-impl<'a, 'j, C> ser::SerializeStructVariant for StructVariantSerializer<'a, 'j, C>
+impl<'j, C> ser::SerializeStructVariant for StructVariantSerializer<'_, 'j, C>
 where
     C: Context<'j>,
 {
@@ -557,13 +767,9 @@ where
     type Error = LibError;
 
     #[inline]
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         use serde::ser::SerializeStruct;
         self.inner.serialize_field(key, value)
@@ -574,3 +780,60 @@ where
         Ok(self.outer_object.upcast())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{MapKey, MapKeySerializer};
+    use crate::errors::Error as LibError;
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Color {
+        Red,
+        Rgb(u8, u8, u8),
+    }
+
+    fn key<T: Serialize>(key: &T) -> MapKey {
+        key.serialize(MapKeySerializer).unwrap()
+    }
+
+    fn name(name: &str) -> MapKey {
+        MapKey::Name(name.to_owned())
+    }
+
+    fn unsupported<T: Serialize>(key: &T) -> &'static str {
+        match key.serialize(MapKeySerializer) {
+            Err(LibError::UnsupportedMapKey { key_type }) => key_type,
+            other => panic!("expected an unsupported key, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_map_keys_are_property_names() {
+        assert_eq!(key(&"name"), name("name"));
+        assert_eq!(key(&42u8), name("42"));
+        assert_eq!(key(&-7i32), name("-7"));
+        // integers keep all of their digits
+        assert_eq!(key(&9_007_199_254_740_993u64), name("9007199254740993"));
+        assert_eq!(
+            key(&i128::MIN),
+            name("-170141183460469231731687303715884105728")
+        );
+        assert_eq!(key(&true), name("true"));
+        assert_eq!(key(&false), name("false"));
+        assert_eq!(key(&'x'), name("x"));
+        assert_eq!(key(&Color::Red), name("Red"));
+        assert_eq!(key(&1.5f64), MapKey::Number(1.5));
+    }
+
+    #[test]
+    fn test_composite_map_keys_are_rejected() {
+        assert_eq!(unsupported(&vec![1, 2]), "seq");
+        assert_eq!(unsupported(&(1, 2)), "tuple");
+        assert_eq!(unsupported(&Color::Rgb(0, 0, 0)), "tuple variant");
+        assert_eq!(unsupported(&BTreeMap::from([(1, 2)])), "map");
+        assert_eq!(unsupported(&Some(1)), "option");
+        assert_eq!(unsupported(&()), "unit");
+    }
+}
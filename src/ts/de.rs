@@ -0,0 +1,415 @@
+//!
+//! A `Deserializer` that records the shape a `Deserialize` impl asks for
+//! and feeds it sample values
+//!
+
+use super::trace::{Format, Tracer, Variant, MAX_DEPTH};
+use crate::errors::Error as LibError;
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{self, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+
+type Result<T> = std::result::Result<T, LibError>;
+
+pub(super) struct TypeDeserializer<'t> {
+    tracer: &'t mut Tracer,
+    format: &'t mut Format,
+}
+
+impl<'t> TypeDeserializer<'t> {
+    pub(super) fn new(tracer: &'t mut Tracer, format: &'t mut Format) -> Self {
+        TypeDeserializer { tracer, format }
+    }
+}
+
+macro_rules! deserialize_sample {
+    ($($method:ident => $format:ident: $visit:ident($($sample:expr)?),)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                *self.format = Format::$format;
+                visitor.$visit($($sample)?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for TypeDeserializer<'_> {
+    type Error = LibError;
+
+    /// The shape depends on the input, e.g. untagged enums or `serde_json::Value`
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        *self.format = Format::Unknown;
+        visitor.visit_unit()
+    }
+
+    deserialize_sample! {
+        deserialize_bool => Boolean: visit_bool(false),
+        deserialize_i8 => Number: visit_i8(1),
+        deserialize_i16 => Number: visit_i16(1),
+        deserialize_i32 => Number: visit_i32(1),
+        deserialize_i64 => Number: visit_i64(1),
+        deserialize_i128 => Number: visit_i128(1),
+        deserialize_u8 => Number: visit_u8(1),
+        deserialize_u16 => Number: visit_u16(1),
+        deserialize_u32 => Number: visit_u32(1),
+        deserialize_u64 => Number: visit_u64(1),
+        deserialize_u128 => Number: visit_u128(1),
+        deserialize_f32 => Number: visit_f32(1.0),
+        deserialize_f64 => Number: visit_f64(1.0),
+        deserialize_char => String: visit_char('a'),
+        deserialize_str => String: visit_borrowed_str(""),
+        deserialize_string => String: visit_string(String::new()),
+        deserialize_identifier => String: visit_borrowed_str(""),
+        deserialize_bytes => Bytes: visit_borrowed_bytes(&[]),
+        deserialize_byte_buf => Bytes: visit_byte_buf(Vec::new()),
+        deserialize_unit => Null: visit_unit(),
+        deserialize_ignored_any => Unknown: visit_unit(),
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    /// `None` inside a recursive container, so the recursion ends
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.tracer.recursion > 0 {
+            *self.format = Format::Option(Box::new(Format::Unknown));
+            return visitor.visit_none();
+        }
+        let mut inner = Format::Unknown;
+        let value = visitor.visit_some(TypeDeserializer::new(self.tracer, &mut inner))?;
+        *self.format = Format::Option(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        #[allow(unused)] name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        #[cfg(feature = "chrono")]
+        if name == crate::date::CHRONO_DATE_TIME_INTERNAL_NAME {
+            let value = visitor
+                .visit_newtype_struct(TypeDeserializer::new(self.tracer, &mut Format::Unknown))?;
+            *self.format = Format::Date;
+            return Ok(value);
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Sequences get a single element, none inside a recursive container
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let len = usize::from(self.tracer.recursion == 0);
+        let mut formats = Vec::new();
+        let value = visitor.visit_seq(Elements::new(self.tracer, &mut formats, len))?;
+        *self.format = Format::Array(Box::new(formats.pop().unwrap_or(Format::Unknown)));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut formats = Vec::new();
+        let value = visitor.visit_seq(Elements::new(self.tracer, &mut formats, len))?;
+        *self.format = Format::Tuple(formats);
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    /// Maps get a single entry, none inside a recursive container
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let len = usize::from(self.tracer.recursion == 0);
+        let mut formats = Vec::new();
+        let value = visitor.visit_map(Entries {
+            elements: Elements::new(self.tracer, &mut formats, len),
+        })?;
+        *self.format = Format::Record(Box::new(formats.pop().unwrap_or(Format::Unknown)));
+        Ok(value)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let recursive = self.tracer.is_recursive(name);
+        if recursive {
+            enter_recursion(self.tracer)?;
+        }
+        self.tracer.enter(name);
+        let mut formats = Vec::new();
+        let value = visitor.visit_map(Fields {
+            tracer: &mut *self.tracer,
+            names: fields,
+            formats: &mut formats,
+        });
+        self.tracer.leave();
+        if recursive {
+            self.tracer.recursion -= 1;
+        }
+
+        let value = value?;
+        self.tracer
+            .record_struct(name, fields.iter().copied().zip(formats).collect());
+        *self.format = Format::Named(name);
+        Ok(value)
+    }
+
+    /// Each pass over the type traces another variant
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let recursive = self.tracer.is_recursive(name);
+        if recursive {
+            enter_recursion(self.tracer)?;
+        }
+        let index = self.tracer.pick_variant(name, variants);
+        let variant = variants
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| de::Error::custom(format!("enum '{name}' has no variants")));
+
+        let value = variant.and_then(|variant| {
+            self.tracer.enter_variant(name, index);
+            let value = visitor.visit_enum(Enum {
+                tracer: &mut *self.tracer,
+                name,
+                index,
+                variant,
+            });
+            self.tracer.leave_variant();
+            value
+        });
+        if recursive {
+            self.tracer.recursion -= 1;
+        }
+
+        *self.format = Format::Named(name);
+        value
+    }
+}
+
+fn enter_recursion(tracer: &mut Tracer) -> Result<()> {
+    if tracer.recursion >= MAX_DEPTH {
+        return Err(de::Error::custom("recursion too deep to trace"));
+    }
+    tracer.recursion += 1;
+    Ok(())
+}
+
+/// `len` elements, each with its own format
+struct Elements<'t> {
+    tracer: &'t mut Tracer,
+    formats: &'t mut Vec<Format>,
+    len: usize,
+}
+
+impl<'t> Elements<'t> {
+    fn new(tracer: &'t mut Tracer, formats: &'t mut Vec<Format>, len: usize) -> Self {
+        Elements {
+            tracer,
+            formats,
+            len,
+        }
+    }
+
+    fn next<'de, T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value> {
+        let mut format = Format::Unknown;
+        let value = seed.deserialize(TypeDeserializer::new(self.tracer, &mut format))?;
+        self.formats.push(format);
+        Ok(value)
+    }
+}
+
+impl<'de> SeqAccess<'de> for Elements<'_> {
+    type Error = LibError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.formats.len() == self.len {
+            return Ok(None);
+        }
+        self.next(seed).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.formats.len())
+    }
+}
+
+/// The keys of a map are strings in JS, only the values are recorded
+struct Entries<'t> {
+    elements: Elements<'t>,
+}
+
+impl<'de> MapAccess<'de> for Entries<'_> {
+    type Error = LibError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.elements.formats.len() == self.elements.len {
+            return Ok(None);
+        }
+        let mut format = Format::Unknown;
+        seed.deserialize(TypeDeserializer::new(self.elements.tracer, &mut format))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.elements.next(seed)
+    }
+}
+
+/// Every field of a struct, by name
+struct Fields<'t> {
+    tracer: &'t mut Tracer,
+    names: &'static [&'static str],
+    formats: &'t mut Vec<Format>,
+}
+
+impl<'de> MapAccess<'de> for Fields<'_> {
+    type Error = LibError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.names.get(self.formats.len()) {
+            Some(field) => seed
+                .deserialize(BorrowedStrDeserializer::<LibError>::new(field))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let mut format = Format::Unknown;
+        let value = seed.deserialize(TypeDeserializer::new(self.tracer, &mut format))?;
+        self.formats.push(format);
+        Ok(value)
+    }
+}
+
+struct Enum<'t> {
+    tracer: &'t mut Tracer,
+    name: &'static str,
+    index: u32,
+    variant: &'static str,
+}
+
+impl<'de> EnumAccess<'de> for Enum<'_> {
+    type Error = LibError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = seed.deserialize(BorrowedStrDeserializer::<LibError>::new(self.variant))?;
+        Ok((value, self))
+    }
+}
+
+impl Enum<'_> {
+    fn record(self, variant: Variant) {
+        self.tracer
+            .record_variant(self.name, self.index, self.variant, variant);
+    }
+}
+
+impl<'de> VariantAccess<'de> for Enum<'_> {
+    type Error = LibError;
+
+    fn unit_variant(self) -> Result<()> {
+        self.record(Variant::Unit);
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let mut format = Format::Unknown;
+        let value = seed.deserialize(TypeDeserializer::new(self.tracer, &mut format))?;
+        self.record(Variant::Newtype(format));
+        Ok(value)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut formats = Vec::new();
+        let value = visitor.visit_seq(Elements::new(self.tracer, &mut formats, len))?;
+        self.record(Variant::Tuple(formats));
+        Ok(value)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut formats = Vec::new();
+        let value = visitor.visit_map(Fields {
+            tracer: &mut *self.tracer,
+            names: fields,
+            formats: &mut formats,
+        })?;
+        self.record(Variant::Struct(
+            fields.iter().copied().zip(formats).collect(),
+        ));
+        Ok(value)
+    }
+}
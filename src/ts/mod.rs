@@ -0,0 +1,701 @@
+//!
+//! TypeScript declarations for `export!` blocks
+//!
+//! `declarations!` lists the exports of an `export!` block, render them
+//! into an `index.d.ts` from a test or a small binary
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn write_declarations() {
+//!     let dts = neon_serde4::ts::render(&neon_serde4::declarations!(crate));
+//!     std::fs::write("index.d.ts", dts).unwrap();
+//! }
+//! ```
+//!
+//! The TypeScript type of a param comes from its `TsType` impl, otherwise
+//! from tracing its `Deserialize` impl with `Tracer`. A return type comes
+//! from its `TsType` impl, otherwise from tracing the `Deserialize` impl of a
+//! type that also implements `Serialize`, which describes the same shape for
+//! derived impls, the `T` of a `Result<T, E>` is described as the `Err` is
+//! thrown. A `const` item is described by tracing its value. Types that are
+//! none of these, or whose `Serialize` and `Deserialize` impls differ, e.g.
+//! with `#[serde(skip_serializing)]`, are declared as `unknown` or need a
+//! `TsType` impl
+//!
+//! ```rust,ignore
+//! impl neon_serde4::ts::TsType for Shape {
+//!     fn ts_type() -> String {
+//!         "\"Empty\" | { Circle: number }".into()
+//!     }
+//! }
+//! ```
+//!
+
+mod de;
+mod ser;
+mod trace;
+
+pub use trace::{accepted_type, produced_type, Format, Tracer};
+
+use crate::callback::JsCallback;
+use neon::handle::Handle;
+use neon::types::{
+    JsArray, JsBoolean, JsBuffer, JsFunction, JsNull, JsNumber, JsObject, JsPromise, JsString,
+    JsUndefined, JsValue, Value,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// An exported item
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item {
+    Function(Function),
+    Const(Const),
+    Class(Class),
+    Namespace(Namespace),
+}
+
+/// An exported function, a class constructor or a method
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub docs: Vec<String>,
+    pub params: Vec<Param>,
+    pub ret: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub ty: String,
+    /// `undefined` is accepted, for `Option`s and params with a default
+    pub optional: bool,
+    /// a `#[rest]` param
+    pub rest: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Const {
+    pub name: String,
+    pub docs: Vec<String>,
+    pub ty: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Class {
+    pub name: String,
+    pub docs: Vec<String>,
+    /// `None` when the class has no `fn new`
+    pub constructor: Option<Function>,
+    pub methods: Vec<Function>,
+    pub statics: Vec<Function>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Namespace {
+    pub name: String,
+    pub docs: Vec<String>,
+    pub items: Vec<Item>,
+}
+
+/// Renders `items` as the contents of a `.d.ts` file
+#[must_use]
+pub fn render(items: &[Item]) -> String {
+    let mut out = String::new();
+    for item in items {
+        render_item(&mut out, item, "", "export declare ");
+    }
+    out
+}
+
+fn render_item(out: &mut String, item: &Item, indent: &str, export: &str) {
+    match item {
+        Item::Function(function) => {
+            render_docs(out, &function.docs, indent);
+            let _ = writeln!(
+                out,
+                "{indent}{export}function {}({}): {};",
+                function.name,
+                render_params(&function.params),
+                function.ret
+            );
+        }
+        Item::Const(constant) => {
+            render_docs(out, &constant.docs, indent);
+            let _ = writeln!(
+                out,
+                "{indent}{export}const {}: {};",
+                constant.name, constant.ty
+            );
+        }
+        Item::Class(class) => {
+            render_docs(out, &class.docs, indent);
+            let _ = writeln!(out, "{indent}{export}class {} {{", class.name);
+            let inner = format!("{indent}    ");
+            match &class.constructor {
+                Some(constructor) => {
+                    render_docs(out, &constructor.docs, &inner);
+                    let _ = writeln!(
+                        out,
+                        "{inner}constructor({});",
+                        render_params(&constructor.params)
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "{inner}private constructor();");
+                }
+            }
+            for (prefix, function) in class
+                .statics
+                .iter()
+                .map(|function| ("static ", function))
+                .chain(class.methods.iter().map(|function| ("", function)))
+            {
+                render_docs(out, &function.docs, &inner);
+                let _ = writeln!(
+                    out,
+                    "{inner}{prefix}{}({}): {};",
+                    function.name,
+                    render_params(&function.params),
+                    function.ret
+                );
+            }
+            let _ = writeln!(out, "{indent}}}");
+        }
+        Item::Namespace(namespace) => {
+            render_docs(out, &namespace.docs, indent);
+            let _ = writeln!(out, "{indent}{export}namespace {} {{", namespace.name);
+            let inner = format!("{indent}    ");
+            for item in &namespace.items {
+                render_item(out, item, &inner, "export ");
+            }
+            let _ = writeln!(out, "{indent}}}");
+        }
+    }
+}
+
+fn render_params(params: &[Param]) -> String {
+    params
+        .iter()
+        .map(|param| {
+            if param.rest {
+                format!("...{}: {}", param.name, param.ty)
+            } else if param.optional {
+                format!("{}?: {}", param.name, param.ty)
+            } else {
+                format!("{}: {}", param.name, param.ty)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn render_docs(out: &mut String, docs: &[String], indent: &str) {
+    if docs.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "{indent}/**");
+    for line in docs {
+        // `///` comments keep the space after the slashes
+        let line = line.strip_prefix(' ').unwrap_or(line).trim_end();
+        if line.is_empty() {
+            let _ = writeln!(out, "{indent} *");
+        } else {
+            let _ = writeln!(out, "{indent} * {line}");
+        }
+    }
+    let _ = writeln!(out, "{indent} */");
+}
+
+/// The single object param of an `#[options]` function
+#[doc(hidden)]
+#[must_use]
+pub fn options(params: &[Param]) -> Vec<Param> {
+    let optional = params.iter().all(|param| param.optional || param.rest);
+    let fields = params
+        .iter()
+        .map(|param| {
+            if param.optional || param.rest {
+                format!("{}?: {}", param.name, param.ty)
+            } else {
+                format!("{}: {}", param.name, param.ty)
+            }
+        })
+        .collect::<Vec<_>>();
+    vec![Param {
+        name: "options".into(),
+        ty: format!("{{ {} }}", fields.join("; ")),
+        optional,
+        rest: false,
+    }]
+}
+
+/// A type with a known TypeScript type, matching what `to_value` produces
+/// and `from_value` accepts
+pub trait TsType {
+    fn ts_type() -> String;
+
+    /// Whether `undefined` is accepted for a param of this type
+    #[must_use]
+    fn optional() -> bool {
+        false
+    }
+}
+
+macro_rules! ts_type {
+    ($ts:literal: $($rust:ty),*) => {
+        $(
+            impl TsType for $rust {
+                fn ts_type() -> String {
+                    $ts.into()
+                }
+            }
+        )*
+    };
+}
+
+ts_type!("boolean": bool);
+ts_type!("number": i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+ts_type!("string": char, str, String);
+ts_type!("null": ());
+
+ts_type!("unknown": JsValue);
+ts_type!("boolean": JsBoolean);
+ts_type!("number": JsNumber);
+ts_type!("string": JsString);
+ts_type!("object": JsObject);
+ts_type!("unknown[]": JsArray);
+ts_type!("Buffer": JsBuffer);
+ts_type!("(...args: any[]) => any": JsFunction);
+ts_type!("Promise<unknown>": JsPromise);
+ts_type!("undefined": JsUndefined);
+ts_type!("null": JsNull);
+ts_type!("unknown": crate::value::Value);
+ts_type!(
+    "((event: string, payload: any) => void) | { emit(event: string, payload: any): unknown }":
+    crate::emitter::Emitter
+);
+
+impl<T: TsType> TsType for Option<T> {
+    fn ts_type() -> String {
+        format!("{} | null", T::ts_type())
+    }
+
+    fn optional() -> bool {
+        true
+    }
+}
+
+/// An `Err` is thrown
+impl<T: TsType, E> TsType for Result<T, E> {
+    fn ts_type() -> String {
+        T::ts_type()
+    }
+}
+
+impl<V: Value + TsType> TsType for Handle<'_, V> {
+    fn ts_type() -> String {
+        V::ts_type()
+    }
+}
+
+/// `(...args: [A, B]) => R`, a `()` return is `void`
+impl<Args: TsType, Ret: TsType> TsType for JsCallback<Args, Ret> {
+    fn ts_type() -> String {
+        let params = match Args::ts_type().as_str() {
+            "null" => String::new(),
+            args => format!("...args: {args}"),
+        };
+        let ret = match Ret::ts_type().as_str() {
+            "null" => String::from("void"),
+            ret => ret.to_owned(),
+        };
+        format!("({params}) => {ret}")
+    }
+}
+
+macro_rules! ts_type_array {
+    ($($rust:ty),*) => {
+        $(
+            impl<T: TsType> TsType for $rust {
+                fn ts_type() -> String {
+                    array(&T::ts_type())
+                }
+            }
+        )*
+    };
+}
+
+ts_type_array!(Vec<T>, VecDeque<T>, [T], HashSet<T>, BTreeSet<T>);
+
+impl<T: TsType, const N: usize> TsType for [T; N] {
+    fn ts_type() -> String {
+        array(&T::ts_type())
+    }
+}
+
+fn array(element: &str) -> String {
+    // unions and function types bind looser than `[]`
+    if element.contains(" | ") || element.contains("=>") {
+        format!("({element})[]")
+    } else {
+        format!("{element}[]")
+    }
+}
+
+macro_rules! ts_type_map {
+    ($($rust:ident),*) => {
+        $(
+            impl<K, V: TsType, S> TsType for $rust<K, V, S> {
+                fn ts_type() -> String {
+                    format!("Record<string, {}>", V::ts_type())
+                }
+            }
+        )*
+    };
+}
+
+ts_type_map!(HashMap);
+
+impl<K, V: TsType> TsType for BTreeMap<K, V> {
+    fn ts_type() -> String {
+        format!("Record<string, {}>", V::ts_type())
+    }
+}
+
+macro_rules! ts_type_deref {
+    ($($rust:ty),*) => {
+        $(
+            impl<T: TsType + ?Sized> TsType for $rust {
+                fn ts_type() -> String {
+                    T::ts_type()
+                }
+
+                fn optional() -> bool {
+                    T::optional()
+                }
+            }
+        )*
+    };
+}
+
+ts_type_deref!(&T, &mut T, Box<T>, Rc<T>, Arc<T>);
+
+impl<T: TsType + ToOwned + ?Sized> TsType for Cow<'_, T> {
+    fn ts_type() -> String {
+        T::ts_type()
+    }
+}
+
+macro_rules! ts_type_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: TsType),+> TsType for ($($name,)+) {
+            fn ts_type() -> String {
+                let elements: &[String] = &[$($name::ts_type()),+];
+                format!("[{}]", elements.join(", "))
+            }
+        }
+    };
+}
+
+ts_type_tuple!(A);
+ts_type_tuple!(A, B);
+ts_type_tuple!(A, B, C);
+ts_type_tuple!(A, B, C, D);
+ts_type_tuple!(A, B, C, D, E);
+ts_type_tuple!(A, B, C, D, E, F);
+
+/// Picks the TypeScript type of `T` in `export!` with autoref
+/// specialization, like the conversions of the returned value
+#[doc(hidden)]
+pub struct Describe<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized> Describe<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Describe(PhantomData)
+    }
+}
+
+impl<T: ?Sized> Default for Describe<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `T` has a `TsType` impl
+#[doc(hidden)]
+pub trait DescribeTsType {
+    fn ts_type(&self) -> String;
+    fn optional(&self) -> bool;
+}
+
+/// The value of a `const` item of a type without a `TsType` impl is traced
+#[doc(hidden)]
+pub trait DescribeProduced<T: ?Sized> {
+    fn value_type(&self, value: &T) -> String;
+}
+
+/// A returned `T` that can be traced, or the `T` of a `Result<T, E>`
+#[doc(hidden)]
+pub trait DescribeReturned {
+    fn ts_type(&self) -> String;
+}
+
+/// `T` can be traced
+#[doc(hidden)]
+pub trait DescribeTraced {
+    fn ts_type(&self) -> String;
+    fn optional(&self) -> bool;
+}
+
+/// Any other type is `unknown`
+#[doc(hidden)]
+pub trait DescribeUnknown {
+    fn ts_type(&self) -> String;
+    fn optional(&self) -> bool;
+}
+
+impl<T: TsType + ?Sized> DescribeTsType for &&&Describe<T> {
+    fn ts_type(&self) -> String {
+        T::ts_type()
+    }
+
+    fn optional(&self) -> bool {
+        T::optional()
+    }
+}
+
+impl<T: TsType + ?Sized> DescribeProduced<T> for &&&Describe<T> {
+    fn value_type(&self, _: &T) -> String {
+        T::ts_type()
+    }
+}
+
+impl<T: Serialize + ?Sized> DescribeProduced<T> for Describe<T> {
+    fn value_type(&self, value: &T) -> String {
+        produced_type(value).unwrap_or_else(|_| "unknown".into())
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, E> DescribeReturned for &&Describe<Result<T, E>> {
+    fn ts_type(&self) -> String {
+        accepted_type::<T>().unwrap_or_else(|_| "unknown".into())
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> DescribeReturned for &Describe<T> {
+    fn ts_type(&self) -> String {
+        accepted_type::<T>().unwrap_or_else(|_| "unknown".into())
+    }
+}
+
+impl<T: DeserializeOwned> DescribeTraced for &Describe<T> {
+    fn ts_type(&self) -> String {
+        accepted_type::<T>().unwrap_or_else(|_| "unknown".into())
+    }
+
+    fn optional(&self) -> bool {
+        matches!(Tracer::new().trace_type::<T>(), Ok(Format::Option(_)))
+    }
+}
+
+impl<T: ?Sized> DescribeUnknown for Describe<T> {
+    fn ts_type(&self) -> String {
+        "unknown".into()
+    }
+
+    fn optional(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        render, Class, Const, Describe, DescribeProduced as _, DescribeTraced as _,
+        DescribeTsType as _, DescribeUnknown as _, Function, HashMap, Item, JsCallback, Namespace,
+        Param, Serialize, TsType,
+    };
+
+    #[test]
+    fn test_ts_types() {
+        assert_eq!(<Vec<Option<u32>>>::ts_type(), "(number | null)[]");
+        assert_eq!(<(String, &[bool])>::ts_type(), "[string, boolean[]]");
+        assert_eq!(
+            <HashMap<String, Vec<f64>>>::ts_type(),
+            "Record<string, number[]>"
+        );
+        assert_eq!(<Result<(), String>>::ts_type(), "null");
+        assert!(<Option<String>>::optional());
+        assert!(!<String>::optional());
+        assert_eq!(
+            <JsCallback<(u32, String), bool>>::ts_type(),
+            "(...args: [number, string]) => boolean"
+        );
+        assert_eq!(<Vec<JsCallback<()>>>::ts_type(), "(() => void)[]");
+    }
+
+    #[derive(Serialize, serde::Deserialize)]
+    struct User {
+        name: String,
+    }
+
+    /// Only serialized, never traced
+    #[derive(Serialize)]
+    struct Event {
+        name: String,
+    }
+
+    /// Describes returned types, with `DescribeReturned` instead of `DescribeTraced`
+    mod returns {
+        use super::super::{
+            Describe, DescribeReturned as _, DescribeTsType as _, DescribeUnknown as _,
+        };
+        use super::{Event, User};
+
+        #[allow(clippy::needless_borrow)]
+        pub fn describe() -> [String; 5] {
+            [
+                (&&&&Describe::<Vec<u32>>::new()).ts_type(),
+                (&&&&Describe::<User>::new()).ts_type(),
+                (&&&&Describe::<Option<Vec<User>>>::new()).ts_type(),
+                (&&&&Describe::<Result<User, std::io::Error>>::new()).ts_type(),
+                (&&&&Describe::<Event>::new()).ts_type(),
+            ]
+        }
+    }
+
+    /// An `export!` block to declare
+    #[allow(dead_code, unused_imports, clippy::unnecessary_wraps)]
+    mod exported {
+        use super::User;
+        use crate::export;
+
+        export! {
+            #![register]
+
+            /// Finds a user by name
+            fn find(name: String) -> Option<User> {
+                Some(User { name })
+            }
+
+            fn load(names: Vec<String>) -> Result<Vec<User>, String> {
+                Ok(names.into_iter().map(|name| User { name }).collect())
+            }
+
+            async fn fetch(name: String) -> User {
+                User { name }
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn test_describe() {
+        // params: `TsType`, then the `Deserialize` trace
+        assert_eq!((&&&&Describe::<u32>::new()).ts_type(), "number");
+        assert_eq!((&&&&Describe::<User>::new()).ts_type(), "{ name: string }");
+        assert!((&&&&Describe::<Option<User>>::new()).optional());
+        assert_eq!((&&&&Describe::<std::fs::File>::new()).ts_type(), "unknown");
+        // returns: `TsType`, then the `Deserialize` trace of `Serialize` types
+        assert_eq!(
+            returns::describe(),
+            [
+                "number[]",
+                "{ name: string }",
+                "{ name: string }[] | null",
+                "{ name: string }",
+                "unknown"
+            ]
+        );
+        // consts: `TsType`, then the traced value
+        let user = User { name: "a".into() };
+        assert_eq!((&&&&Describe::<&str>::new()).value_type(&"a"), "string");
+        assert_eq!(
+            (&&&&Describe::<User>::new()).value_type(&user),
+            "{ name: string }"
+        );
+    }
+
+    #[test]
+    fn test_declarations() {
+        assert_eq!(
+            render(&crate::declarations!(exported)),
+            "/**
+ * Finds a user by name
+ */
+export declare function find(name: string): { name: string } | null;
+export declare function load(names: string[]): { name: string }[];
+export declare function fetch(name: string): Promise<{ name: string }>;
+"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let add = Function {
+            name: "add".into(),
+            docs: vec![" Adds numbers".into()],
+            params: vec![
+                Param {
+                    name: "base".into(),
+                    ty: "number".into(),
+                    optional: true,
+                    rest: false,
+                },
+                Param {
+                    name: "values".into(),
+                    ty: "number[]".into(),
+                    optional: false,
+                    rest: true,
+                },
+            ],
+            ret: "number".into(),
+        };
+        let items = [
+            Item::Const(Const {
+                name: "VERSION".into(),
+                docs: vec![],
+                ty: "string".into(),
+            }),
+            Item::Namespace(Namespace {
+                name: "math".into(),
+                docs: vec![],
+                items: vec![Item::Function(add.clone())],
+            }),
+            Item::Class(Class {
+                name: "Counter".into(),
+                docs: vec![],
+                constructor: None,
+                methods: vec![add],
+                statics: vec![],
+            }),
+        ];
+
+        assert_eq!(
+            render(&items),
+            "export declare const VERSION: string;
+export declare namespace math {
+    /**
+     * Adds numbers
+     */
+    export function add(base?: number, ...values: number[]): number;
+}
+export declare class Counter {
+    private constructor();
+    /**
+     * Adds numbers
+     */
+    add(base?: number, ...values: number[]): number;
+}
+"
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//!
+//! A `Serializer` that records the shape of a sample value
+//!
+
+use super::trace::{Format, Tracer, Variant};
+use crate::errors::Error as LibError;
+use serde::ser::{self, Serialize};
+
+type Result<T> = std::result::Result<T, LibError>;
+
+pub(super) struct ValueSerializer<'t> {
+    tracer: &'t mut Tracer,
+}
+
+impl<'t> ValueSerializer<'t> {
+    pub(super) fn new(tracer: &'t mut Tracer) -> Self {
+        ValueSerializer { tracer }
+    }
+}
+
+macro_rules! serialize_as {
+    ($($method:ident($type:ty) => $format:ident,)*) => {
+        $(
+            fn $method(self, _v: $type) -> Result<Format> {
+                Ok(Format::$format)
+            }
+        )*
+    };
+}
+
+impl<'t> ser::Serializer for ValueSerializer<'t> {
+    type Ok = Format;
+    type Error = LibError;
+
+    type SerializeSeq = Elements<'t>;
+    type SerializeTuple = Elements<'t>;
+    type SerializeTupleStruct = Elements<'t>;
+    type SerializeTupleVariant = VariantElements<'t>;
+    type SerializeMap = Elements<'t>;
+    type SerializeStruct = Fields<'t>;
+    type SerializeStructVariant = VariantFields<'t>;
+
+    serialize_as! {
+        serialize_bool(bool) => Boolean,
+        serialize_i8(i8) => Number,
+        serialize_i16(i16) => Number,
+        serialize_i32(i32) => Number,
+        serialize_i64(i64) => Number,
+        serialize_i128(i128) => Number,
+        serialize_u8(u8) => Number,
+        serialize_u16(u16) => Number,
+        serialize_u32(u32) => Number,
+        serialize_u64(u64) => Number,
+        serialize_u128(u128) => Number,
+        serialize_f32(f32) => Number,
+        serialize_f64(f64) => Number,
+        serialize_char(char) => String,
+        serialize_str(&str) => String,
+        serialize_bytes(&[u8]) => Bytes,
+    }
+
+    fn serialize_none(self) -> Result<Format> {
+        Ok(Format::Option(Box::new(Format::Unknown)))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Format>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(Format::Option(Box::new(value.serialize(self)?)))
+    }
+
+    fn serialize_unit(self) -> Result<Format> {
+        Ok(Format::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Format> {
+        Ok(Format::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Format> {
+        self.tracer
+            .record_variant(name, variant_index, variant, Variant::Unit);
+        Ok(Format::Named(name))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        #[allow(unused)] name: &'static str,
+        value: &T,
+    ) -> Result<Format>
+    where
+        T: ?Sized + Serialize,
+    {
+        #[cfg(feature = "chrono")]
+        if name == crate::date::CHRONO_DATE_TIME_INTERNAL_NAME {
+            return Ok(Format::Date);
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Format>
+    where
+        T: ?Sized + Serialize,
+    {
+        let format = value.serialize(ValueSerializer::new(self.tracer))?;
+        self.tracer
+            .record_variant(name, variant_index, variant, Variant::Newtype(format));
+        Ok(Format::Named(name))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Elements<'t>> {
+        Ok(Elements::new(self.tracer))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Elements<'t>> {
+        Ok(Elements::new(self.tracer))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Elements<'t>> {
+        Ok(Elements::new(self.tracer))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<VariantElements<'t>> {
+        Ok(VariantElements {
+            elements: Elements::new(self.tracer),
+            name,
+            variant_index,
+            variant,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Elements<'t>> {
+        Ok(Elements::new(self.tracer))
+    }
+
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Fields<'t>> {
+        Ok(Fields {
+            tracer: self.tracer,
+            name,
+            formats: Vec::new(),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<VariantFields<'t>> {
+        Ok(VariantFields {
+            fields: Fields {
+                tracer: self.tracer,
+                name,
+                formats: Vec::new(),
+            },
+            variant_index,
+            variant,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct Elements<'t> {
+    tracer: &'t mut Tracer,
+    formats: Vec<Format>,
+}
+
+impl<'t> Elements<'t> {
+    fn new(tracer: &'t mut Tracer) -> Self {
+        Elements {
+            tracer,
+            formats: Vec::new(),
+        }
+    }
+
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        let format = value.serialize(ValueSerializer::new(self.tracer))?;
+        self.formats.push(format);
+        Ok(())
+    }
+
+    /// The elements of an array all have the same shape
+    fn array(self) -> Format {
+        let element = self
+            .formats
+            .into_iter()
+            .reduce(|mut element, format| {
+                element.merge(format);
+                element
+            })
+            .unwrap_or(Format::Unknown);
+        Format::Array(Box::new(element))
+    }
+}
+
+impl ser::SerializeSeq for Elements<'_> {
+    type Ok = Format;
+    type Error = LibError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Format> {
+        Ok(self.array())
+    }
+}
+
+impl ser::SerializeTuple for Elements<'_> {
+    type Ok = Format;
+    type Error = LibError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Format> {
+        Ok(Format::Tuple(self.formats))
+    }
+}
+
+impl ser::SerializeTupleStruct for Elements<'_> {
+    type Ok = Format;
+    type Error = LibError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Format> {
+        Ok(Format::Tuple(self.formats))
+    }
+}
+
+/// Keys are strings in JS, only the values are recorded
+impl ser::SerializeMap for Elements<'_> {
+    type Ok = Format;
+    type Error = LibError;
+
+    fn serialize_key<T>(&mut self, _key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Format> {
+        match self.array() {
+            Format::Array(value) => Ok(Format::Record(value)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct VariantElements<'t> {
+    elements: Elements<'t>,
+    name: &'static str,
+    variant_index: u32,
+    variant: &'static str,
+}
+
+impl ser::SerializeTupleVariant for VariantElements<'_> {
+    type Ok = Format;
+    type Error = LibError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.elements.push(value)
+    }
+
+    fn end(self) -> Result<Format> {
+        let Elements { tracer, formats } = self.elements;
+        tracer.record_variant(
+            self.name,
+            self.variant_index,
+            self.variant,
+            Variant::Tuple(formats),
+        );
+        Ok(Format::Named(self.name))
+    }
+}
+
+#[doc(hidden)]
+pub struct Fields<'t> {
+    tracer: &'t mut Tracer,
+    name: &'static str,
+    formats: Vec<(&'static str, Format)>,
+}
+
+impl Fields<'_> {
+    fn push<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        let format = value.serialize(ValueSerializer::new(self.tracer))?;
+        self.formats.push((key, format));
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for Fields<'_> {
+    type Ok = Format;
+    type Error = LibError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<Format> {
+        self.tracer.record_struct(self.name, self.formats);
+        Ok(Format::Named(self.name))
+    }
+}
+
+#[doc(hidden)]
+pub struct VariantFields<'t> {
+    fields: Fields<'t>,
+    variant_index: u32,
+    variant: &'static str,
+}
+
+impl ser::SerializeStructVariant for VariantFields<'_> {
+    type Ok = Format;
+    type Error = LibError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.fields.push(key, value)
+    }
+
+    fn end(self) -> Result<Format> {
+        let Fields {
+            tracer,
+            name,
+            formats,
+        } = self.fields;
+        tracer.record_variant(
+            name,
+            self.variant_index,
+            self.variant,
+            Variant::Struct(formats),
+        );
+        Ok(Format::Named(name))
+    }
+}
@@ -0,0 +1,533 @@
+//!
+//! Traces serde impls into the shape of the JS values this crate converts
+//! them from and to, in the spirit of `serde-reflection`
+//!
+//! `Deserialize` impls are traced from the type alone, driven with sample
+//! values until every enum variant was seen, `Serialize` impls need sample
+//! values and only record the variants those samples use
+//!
+
+use super::de::TypeDeserializer;
+use super::ser::ValueSerializer;
+use crate::errors::Result as LibResult;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Recursive containers nested deeper than this fail the trace
+pub(super) const MAX_DEPTH: usize = 32;
+
+/// Tracing stops after this many passes over a `Deserialize` impl
+const MAX_PASSES: usize = 256;
+
+/// The shape of a JS value converted by this crate
+#[derive(Clone, Debug, PartialEq)]
+pub enum Format {
+    /// Not known, e.g. from `deserialize_any`
+    Unknown,
+    /// `()`, unit structs and `None`
+    Null,
+    Boolean,
+    Number,
+    String,
+    /// Bytes are a `Buffer`
+    Bytes,
+    /// A `date::JsDate` field
+    Date,
+    Option(Box<Format>),
+    Array(Box<Format>),
+    Tuple(Vec<Format>),
+    /// A map is an object with arbitrary keys
+    Record(Box<Format>),
+    /// A struct or an enum recorded by the tracer under its name
+    Named(&'static str),
+}
+
+impl Format {
+    /// Fills in what `other` knows and `self` does not
+    pub(super) fn merge(&mut self, other: Format) {
+        match (self, other) {
+            (_, Format::Unknown) => {}
+            (this @ Format::Unknown, other) => *this = other,
+            (Format::Option(this), Format::Option(other))
+            | (Format::Array(this), Format::Array(other))
+            | (Format::Record(this), Format::Record(other)) => this.merge(*other),
+            (Format::Option(this), other) => this.merge(other),
+            (this, Format::Option(other)) => {
+                let mut inner = std::mem::replace(this, Format::Unknown);
+                inner.merge(*other);
+                *this = Format::Option(Box::new(inner));
+            }
+            (Format::Tuple(this), Format::Tuple(other)) => merge_all(this, other),
+            _ => {}
+        }
+    }
+}
+
+fn merge_all(this: &mut [Format], other: Vec<Format>) {
+    for (this, other) in this.iter_mut().zip(other) {
+        this.merge(other);
+    }
+}
+
+fn merge_fields(this: &mut Vec<(&'static str, Format)>, other: Vec<(&'static str, Format)>) {
+    for (name, format) in other {
+        match this.iter_mut().find(|(field, _)| *field == name) {
+            Some((_, this)) => this.merge(format),
+            None => this.push((name, format)),
+        }
+    }
+}
+
+/// A named struct or enum
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Container {
+    Struct(Vec<(&'static str, Format)>),
+    /// The variants by index, `None` while a variant was not traced
+    Enum(BTreeMap<u32, (&'static str, Option<Variant>)>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Variant {
+    Unit,
+    Newtype(Format),
+    Tuple(Vec<Format>),
+    Struct(Vec<(&'static str, Format)>),
+}
+
+impl Variant {
+    fn merge(&mut self, other: Variant) {
+        match (self, other) {
+            (Variant::Newtype(this), Variant::Newtype(other)) => this.merge(other),
+            (Variant::Tuple(this), Variant::Tuple(other)) => merge_all(this, other),
+            (Variant::Struct(this), Variant::Struct(other)) => merge_fields(this, other),
+            _ => {}
+        }
+    }
+}
+
+/// Records the structs and enums met while tracing, several types or
+/// samples can be traced with the same `Tracer`
+#[derive(Debug, Default)]
+pub struct Tracer {
+    containers: BTreeMap<&'static str, Container>,
+    /// containers being traced, entering one again is recursion
+    stack: Vec<&'static str>,
+    /// variants being traced
+    variants: Vec<(&'static str, u32)>,
+    /// variants picked in a previous pass, the next pass picks another one
+    attempted: BTreeSet<(&'static str, u32)>,
+    /// how deep inside recursive containers the tracer is, nothing is
+    /// recorded there and values are kept as small as possible
+    pub(super) recursion: usize,
+}
+
+impl Tracer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Traces the values `from_value` accepts for `T`
+    ///
+    /// # Errors
+    ///
+    /// When `T` rejects every sample value, e.g. `deserialize_any` based
+    /// impls such as untagged enums
+    pub fn trace_type<T: DeserializeOwned>(&mut self) -> LibResult<Format> {
+        let mut traced = None;
+        let mut error = None;
+        for _ in 0..MAX_PASSES {
+            let attempted = self.attempted.len();
+            let mut format = Format::Unknown;
+            match T::deserialize(TypeDeserializer::new(self, &mut format)) {
+                Ok(_) => match &mut traced {
+                    Some(traced) => Format::merge(traced, format),
+                    None => traced = Some(format),
+                },
+                Err(err) => error = Some(err),
+            }
+            // every pass tries a variant that was not picked before
+            if !self.has_pending_variants() || self.attempted.len() == attempted {
+                break;
+            }
+        }
+        match (traced, error) {
+            (Some(format), _) => Ok(format),
+            (None, Some(err)) => Err(err),
+            (None, None) => Ok(Format::Unknown),
+        }
+    }
+
+    /// Traces the value `to_value` produces for `value`
+    ///
+    /// # Errors
+    ///
+    /// When `value` fails to serialize
+    pub fn trace_value<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<Format> {
+        value.serialize(ValueSerializer::new(self))
+    }
+
+    /// The TypeScript type of `format`, recursive types are `unknown` where
+    /// they refer to themselves
+    #[must_use]
+    pub fn ts_type(&self, format: &Format) -> String {
+        self.render(format, &mut Vec::new())
+    }
+
+    pub(super) fn is_recursive(&self, name: &'static str) -> bool {
+        self.stack.contains(&name)
+    }
+
+    pub(super) fn enter(&mut self, name: &'static str) {
+        self.stack.push(name);
+    }
+
+    pub(super) fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    pub(super) fn record_struct(
+        &mut self,
+        name: &'static str,
+        fields: Vec<(&'static str, Format)>,
+    ) {
+        if self.recursion > 0 {
+            return;
+        }
+        match self.containers.get_mut(name) {
+            Some(Container::Struct(this)) => merge_fields(this, fields),
+            _ => {
+                self.containers.insert(name, Container::Struct(fields));
+            }
+        }
+    }
+
+    /// Picks the variant of the enum `name` to trace next, each pass picks
+    /// one that was not picked yet, inside a recursive container a unit
+    /// variant if there is one
+    pub(super) fn pick_variant(
+        &mut self,
+        name: &'static str,
+        variants: &'static [&'static str],
+    ) -> u32 {
+        let known = enum_variants(&mut self.containers, name);
+        for (index, variant) in (0..).zip(variants) {
+            known.entry(index).or_insert((variant, None));
+        }
+
+        let known = &*known;
+        let in_progress = |index: &u32| self.variants.contains(&(name, *index));
+        let unit = known
+            .iter()
+            .find(|(_, (_, variant))| matches!(variant, Some(Variant::Unit)))
+            .map(|(index, _)| *index);
+        let pending = known
+            .keys()
+            .find(|index| !self.attempted.contains(&(name, **index)) && !in_progress(index))
+            .copied();
+        let traced = known
+            .iter()
+            .find(|(index, (_, variant))| variant.is_some() && !in_progress(index))
+            .map(|(index, _)| *index);
+
+        let index = if self.recursion > 0 {
+            unit.or(pending).or(traced)
+        } else {
+            pending.or(traced)
+        }
+        .unwrap_or(0);
+        if self.recursion == 0 {
+            self.attempted.insert((name, index));
+        }
+        index
+    }
+
+    pub(super) fn enter_variant(&mut self, name: &'static str, index: u32) {
+        self.stack.push(name);
+        self.variants.push((name, index));
+    }
+
+    pub(super) fn leave_variant(&mut self) {
+        self.stack.pop();
+        self.variants.pop();
+    }
+
+    pub(super) fn record_variant(
+        &mut self,
+        name: &'static str,
+        index: u32,
+        variant_name: &'static str,
+        variant: Variant,
+    ) {
+        if self.recursion > 0 {
+            return;
+        }
+        let entry = enum_variants(&mut self.containers, name)
+            .entry(index)
+            .or_insert((variant_name, None));
+        match &mut entry.1 {
+            Some(this) => this.merge(variant),
+            None => entry.1 = Some(variant),
+        }
+    }
+
+    fn has_pending_variants(&self) -> bool {
+        self.containers
+            .iter()
+            .any(|(name, container)| match container {
+                Container::Enum(variants) => variants
+                    .keys()
+                    .any(|index| !self.attempted.contains(&(*name, *index))),
+                Container::Struct(_) => false,
+            })
+    }
+
+    fn render(&self, format: &Format, stack: &mut Vec<&'static str>) -> String {
+        match format {
+            Format::Unknown => "unknown".into(),
+            Format::Null => "null".into(),
+            Format::Boolean => "boolean".into(),
+            Format::Number => "number".into(),
+            Format::String => "string".into(),
+            Format::Bytes => "Buffer".into(),
+            Format::Date => "Date".into(),
+            Format::Option(format) => match **format {
+                // `Option<()>` is `null` either way
+                Format::Null => "null".into(),
+                _ => format!("{} | null", self.render(format, stack)),
+            },
+            Format::Array(format) => super::array(&self.render(format, stack)),
+            Format::Tuple(formats) => format!("[{}]", self.render_all(formats, stack)),
+            Format::Record(format) => format!("Record<string, {}>", self.render(format, stack)),
+            Format::Named(name) if stack.contains(name) => "unknown".into(),
+            Format::Named(name) => {
+                stack.push(name);
+                let rendered = match self.containers.get(name) {
+                    Some(Container::Struct(fields)) => self.render_fields(fields, stack),
+                    Some(Container::Enum(variants)) => self.render_variants(variants, stack),
+                    None => "unknown".into(),
+                };
+                stack.pop();
+                rendered
+            }
+        }
+    }
+
+    fn render_all(&self, formats: &[Format], stack: &mut Vec<&'static str>) -> String {
+        formats
+            .iter()
+            .map(|format| self.render(format, stack))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// `Option` fields may be missing in the object
+    fn render_fields(
+        &self,
+        fields: &[(&'static str, Format)],
+        stack: &mut Vec<&'static str>,
+    ) -> String {
+        if fields.is_empty() {
+            return "{}".into();
+        }
+        let fields = fields
+            .iter()
+            .map(|(name, format)| {
+                let optional = if let Format::Option(_) = format {
+                    "?"
+                } else {
+                    ""
+                };
+                format!(
+                    "{}{optional}: {}",
+                    property_name(name),
+                    self.render(format, stack)
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("{{ {} }}", fields.join("; "))
+    }
+
+    /// Enums are externally tagged, a unit variant is its name
+    fn render_variants(
+        &self,
+        variants: &BTreeMap<u32, (&'static str, Option<Variant>)>,
+        stack: &mut Vec<&'static str>,
+    ) -> String {
+        if variants.is_empty() {
+            return "never".into();
+        }
+        variants
+            .values()
+            .map(|(name, variant)| {
+                let value = match variant {
+                    Some(Variant::Unit) => return format!("{name:?}"),
+                    Some(Variant::Newtype(format)) => self.render(format, stack),
+                    Some(Variant::Tuple(formats)) => {
+                        format!("[{}]", self.render_all(formats, stack))
+                    }
+                    Some(Variant::Struct(fields)) => self.render_fields(fields, stack),
+                    None => "unknown".into(),
+                };
+                format!("{{ {}: {value} }}", property_name(name))
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+/// The variants recorded for the enum `name`
+fn enum_variants<'a>(
+    containers: &'a mut BTreeMap<&'static str, Container>,
+    name: &'static str,
+) -> &'a mut BTreeMap<u32, (&'static str, Option<Variant>)> {
+    let container = containers
+        .entry(name)
+        .or_insert_with(|| Container::Enum(BTreeMap::new()));
+    if let Container::Struct(_) = container {
+        *container = Container::Enum(BTreeMap::new());
+    }
+    let Container::Enum(variants) = container else {
+        unreachable!()
+    };
+    variants
+}
+
+/// Quotes property names that are not identifiers
+fn property_name(name: &str) -> String {
+    let identifier = name.chars().enumerate().all(|(i, c)| {
+        c == '_' || c == '$' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit())
+    });
+    if identifier && !name.is_empty() {
+        name.into()
+    } else {
+        format!("{name:?}")
+    }
+}
+
+/// The TypeScript type of the values `from_value` accepts for `T`
+///
+/// # Errors
+///
+/// When `T` can not be traced, see `Tracer::trace_type`
+pub fn accepted_type<T: DeserializeOwned>() -> LibResult<String> {
+    let mut tracer = Tracer::new();
+    let format = tracer.trace_type::<T>()?;
+    Ok(tracer.ts_type(&format))
+}
+
+/// The TypeScript type of the value `to_value` produces for `value`
+///
+/// # Errors
+///
+/// When `value` fails to serialize
+pub fn produced_type<T: Serialize + ?Sized>(value: &T) -> LibResult<String> {
+    let mut tracer = Tracer::new();
+    let format = tracer.trace_value(value)?;
+    Ok(tracer.ts_type(&format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    #[derive(Serialize, Deserialize)]
+    #[allow(dead_code)]
+    struct User {
+        name: String,
+        age: Option<u16>,
+        tags: Vec<String>,
+        #[serde(rename = "last-seen")]
+        last_seen: (u32, bool),
+        scores: HashMap<String, f64>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[allow(dead_code)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Line(u32, u32),
+        Rect { width: f64, height: f64 },
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    enum Expr {
+        Add(Box<Expr>, Box<Expr>),
+        Num(f64),
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Tree {
+        children: Vec<Tree>,
+        parent: Option<Box<Tree>>,
+    }
+
+    #[test]
+    fn test_trace_struct() {
+        assert_eq!(
+            accepted_type::<User>().unwrap(),
+            "{ name: string; age?: number | null; tags: string[]; \
+             \"last-seen\": [number, boolean]; scores: Record<string, number> }"
+        );
+    }
+
+    #[test]
+    fn test_trace_enum() {
+        assert_eq!(
+            accepted_type::<Vec<Shape>>().unwrap(),
+            "(\"Empty\" | { Circle: number } | { Line: [number, number] } \
+             | { Rect: { width: number; height: number } })[]"
+        );
+    }
+
+    #[test]
+    fn test_trace_recursive() {
+        assert_eq!(
+            accepted_type::<Expr>().unwrap(),
+            "{ Add: [unknown, unknown] } | { Num: number }"
+        );
+        assert_eq!(
+            accepted_type::<Tree>().unwrap(),
+            "{ children: unknown[]; parent?: unknown | null }"
+        );
+    }
+
+    #[test]
+    fn test_trace_value() {
+        let mut tracer = Tracer::new();
+        let circle = tracer.trace_value(&Shape::Circle(1.0)).unwrap();
+        tracer.trace_value(&Shape::Empty).unwrap();
+        assert_eq!(tracer.ts_type(&circle), "\"Empty\" | { Circle: number }");
+
+        assert_eq!(
+            produced_type(&(Some(1u8), None::<String>, ())).unwrap(),
+            "[number | null, unknown | null, null]"
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_trace_date() {
+        use crate::date::JsDate;
+        use chrono::{DateTime, Utc};
+
+        #[serde_with::serde_as]
+        #[derive(Serialize, Deserialize)]
+        struct Event {
+            #[serde_as(as = "JsDate")]
+            at: DateTime<Utc>,
+        }
+
+        assert_eq!(accepted_type::<Event>().unwrap(), "{ at: Date }");
+        let event = Event {
+            at: DateTime::from_timestamp_millis(0).unwrap(),
+        };
+        assert_eq!(produced_type(&event).unwrap(), "{ at: Date }");
+    }
+}
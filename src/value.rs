@@ -0,0 +1,1081 @@
+//!
+//! A `Send` tree of JS values, the serde walk runs on any thread and only
+//! the conversion from and to handles needs the JS thread
+//!
+
+use crate::de::{number_to_string, visit_number, MapKeyDeserializer};
+use crate::errors::{Error as LibError, Result as LibResult};
+use crate::ser::{MapKey, MapKeySerializer};
+use neon::prelude::*;
+use neon::types::buffer::TypedArray;
+use neon::types::{JsBigInt, JsDate};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    Unexpected, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq};
+use std::fmt;
+use std::vec;
+
+/// Arrays and objects nested deeper than this are not read by `Value::from_js`
+const MAX_DEPTH: usize = 128;
+
+/// A JS value owned by Rust
+///
+/// `Value::from_serialize` gives the same value as `to_value` and
+/// `deserialize_into` accepts the same values as `from_value`, neither needs
+/// a `Context`. `to_js` and `from_js` convert from and to handles in one pass
+///
+/// ```rust,no_run
+/// # use neon_serde4 as neon_serde;
+/// use neon::prelude::*;
+/// use neon_serde::Value;
+///
+/// fn primes(mut cx: FunctionContext) -> JsResult<JsPromise> {
+///     let below = cx.argument::<JsNumber>(0)?.value(&mut cx) as u32;
+///     let promise = cx.task(move || {
+///         let primes: Vec<u32> = (2..below).filter(|n| (2..*n).all(|d| n % d != 0)).collect();
+///         // `Error` is not `Send`, only its message leaves the task
+///         Value::from_serialize(&primes).map_err(|err| err.to_string())
+///     })
+///     .promise(|mut cx, primes| match primes {
+///         Ok(primes) => primes.to_js(&mut cx).or_else(|err| cx.throw_error(err.to_string())),
+///         Err(msg) => cx.throw_error(msg),
+///     });
+///     Ok(promise)
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Value {
+    /// `null`, also read from `undefined`
+    #[default]
+    Null,
+    Bool(bool),
+    Number(f64),
+    BigInt(i128),
+    String(String),
+    /// A `Buffer`
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    /// The own properties of an object in order
+    Object(Vec<(String, Value)>),
+    /// A `Date` as milliseconds since the epoch
+    Date(f64),
+}
+
+impl Value {
+    /// Serializes `value` the way `to_value` does
+    ///
+    /// # Errors
+    ///
+    /// * `UnsupportedMapKey` if a map key is not a string, number, bool, char or unit variant
+    /// * any error of the `Serialize` impl
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> LibResult<Self> {
+        value.serialize(ValueSerializer)
+    }
+
+    /// Deserializes a `T` the way `from_value` does
+    ///
+    /// # Errors
+    ///
+    /// Can fail for various reasons see `Error`
+    pub fn deserialize_into<T: DeserializeOwned>(self) -> LibResult<T> {
+        T::deserialize(self)
+    }
+
+    /// Reads `value` and everything it contains, objects such as a `Map` or
+    /// class instances are read as their own enumerable properties
+    ///
+    /// # Errors
+    ///
+    /// * `Js` if reading a property threw
+    /// * `Deserialize` for a `BigInt` out of the range of `i128`, a function
+    ///   or symbol, a cyclic value or one nested deeper than 128 levels
+    pub fn from_js<'j, C: Context<'j>>(cx: &mut C, value: Handle<'j, JsValue>) -> LibResult<Self> {
+        Value::read(cx, value, &mut Vec::new())
+    }
+
+    /// Reads `value`, `ancestors` are the arrays and objects it is in
+    fn read<'j, C: Context<'j>>(
+        cx: &mut C,
+        value: Handle<'j, JsValue>,
+        ancestors: &mut Vec<Handle<'j, JsObject>>,
+    ) -> LibResult<Self> {
+        if value.is_a::<JsNull, _>(cx) || value.is_a::<JsUndefined, _>(cx) {
+            Ok(Value::Null)
+        } else if let Ok(val) = value.downcast::<JsBoolean, _>(cx) {
+            Ok(Value::Bool(val.value(cx)))
+        } else if let Ok(val) = value.downcast::<JsNumber, _>(cx) {
+            Ok(Value::Number(val.value(cx)))
+        } else if let Ok(val) = value.downcast::<JsBigInt, _>(cx) {
+            val.to_i128(cx)
+                .map(Value::BigInt)
+                .map_err(|_| de::Error::custom("BigInt out of the range of i128"))
+        } else if let Ok(val) = value.downcast::<JsString, _>(cx) {
+            Ok(Value::String(val.value(cx)))
+        } else if let Ok(val) = value.downcast::<JsBuffer, _>(cx) {
+            Ok(Value::Bytes(val.as_slice(cx).to_vec()))
+        } else if let Ok(val) = value.downcast::<JsDate, _>(cx) {
+            Ok(Value::Date(val.value(cx)))
+        } else if value.is_a::<JsFunction, _>(cx) {
+            Err(de::Error::custom("a function can not be read as a value"))
+        } else if let Ok(val) = value.downcast::<JsObject, _>(cx) {
+            Value::enter(cx, val, ancestors)?;
+            let read = if let Ok(val) = value.downcast::<JsArray, _>(cx) {
+                Value::read_array(cx, val, ancestors)
+            } else {
+                Value::read_object(cx, val, ancestors)
+            };
+            ancestors.pop();
+            read
+        } else {
+            Err(de::Error::custom("a symbol can not be read as a value"))
+        }
+    }
+
+    /// Pushes `object` onto `ancestors` unless that makes a cycle or goes
+    /// deeper than `MAX_DEPTH`
+    fn enter<'j, C: Context<'j>>(
+        cx: &mut C,
+        object: Handle<'j, JsObject>,
+        ancestors: &mut Vec<Handle<'j, JsObject>>,
+    ) -> LibResult<()> {
+        if ancestors.len() == MAX_DEPTH {
+            return Err(de::Error::custom(format_args!(
+                "value nested deeper than {MAX_DEPTH} levels"
+            )));
+        }
+        if ancestors
+            .iter()
+            .any(|ancestor| ancestor.strict_equals(cx, object))
+        {
+            return Err(de::Error::custom("cyclic value"));
+        }
+        ancestors.push(object);
+        Ok(())
+    }
+
+    fn read_array<'j, C: Context<'j>>(
+        cx: &mut C,
+        array: Handle<'j, JsArray>,
+        ancestors: &mut Vec<Handle<'j, JsObject>>,
+    ) -> LibResult<Self> {
+        let len = array.len(cx);
+        let mut elements = Vec::with_capacity(len as usize);
+        for i in 0..len {
+            let element = array.get_value(cx, i)?;
+            elements.push(Value::read(cx, element, ancestors)?);
+        }
+        Ok(Value::Array(elements))
+    }
+
+    fn read_object<'j, C: Context<'j>>(
+        cx: &mut C,
+        object: Handle<'j, JsObject>,
+        ancestors: &mut Vec<Handle<'j, JsObject>>,
+    ) -> LibResult<Self> {
+        let keys = object.get_own_property_names(cx)?;
+        let len = keys.len(cx);
+        let mut properties = Vec::with_capacity(len as usize);
+        for i in 0..len {
+            let key = keys.get::<JsString, _, _>(cx, i)?;
+            let property = object.get_value(cx, key)?;
+            properties.push((key.value(cx), Value::read(cx, property, ancestors)?));
+        }
+        Ok(Value::Object(properties))
+    }
+
+    /// Builds the JS value
+    ///
+    /// # Errors
+    ///
+    /// * `StringTooLong` if a string exceeds v8's max string size
+    /// * `Serialize` for a `Date` out of the range of JS dates
+    pub fn to_js<'j, C: Context<'j>>(&self, cx: &mut C) -> LibResult<Handle<'j, JsValue>> {
+        Ok(match self {
+            Value::Null => cx.null().upcast(),
+            Value::Bool(v) => cx.boolean(*v).upcast(),
+            Value::Number(v) => cx.number(*v).upcast(),
+            Value::BigInt(v) => JsBigInt::from_i128(cx, *v).upcast(),
+            Value::String(v) => JsString::try_new(cx, v)
+                .map_err(|_| LibError::StringTooLong { len: v.len() })?
+                .upcast(),
+            Value::Bytes(v) => JsBuffer::from_slice(cx, v)?.upcast(),
+            Value::Array(elements) => {
+                let array = JsArray::new(cx, elements.len());
+                for (i, element) in (0..).zip(elements) {
+                    let element = element.to_js(cx)?;
+                    array.set(cx, i, element)?;
+                }
+                array.upcast()
+            }
+            Value::Object(properties) => {
+                let object = cx.empty_object();
+                for (key, property) in properties {
+                    let property = property.to_js(cx)?;
+                    object.set(cx, key.as_str(), property)?;
+                }
+                object.upcast()
+            }
+            Value::Date(v) => cx
+                .date(*v)
+                .map_err(<LibError as ser::Error>::custom)?
+                .upcast(),
+        })
+    }
+
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Value::Null => Unexpected::Other("null"),
+            Value::Bool(v) => Unexpected::Bool(*v),
+            Value::Number(v) => Unexpected::Float(*v),
+            Value::BigInt(_) => Unexpected::Other("BigInt"),
+            Value::String(v) => Unexpected::Str(v),
+            Value::Bytes(v) => Unexpected::Bytes(v),
+            Value::Array(_) => Unexpected::Seq,
+            Value::Object(_) => Unexpected::Map,
+            Value::Date(_) => Unexpected::Other("Date"),
+        }
+    }
+}
+
+/// A `Date` is a chrono date with the `chrono` feature and its timestamp
+/// otherwise, a `BigInt` is an `i128`
+impl Serialize for Value {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::Number(v) => serializer.serialize_f64(*v),
+            Value::BigInt(v) => serializer.serialize_i128(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Bytes(v) => serializer.serialize_bytes(v),
+            Value::Array(elements) => {
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for element in elements {
+                    seq.serialize_element(element)?;
+                }
+                seq.end()
+            }
+            Value::Object(properties) => {
+                let mut map = serializer.serialize_map(Some(properties.len()))?;
+                for (key, property) in properties {
+                    map.serialize_entry(key, property)?;
+                }
+                map.end()
+            }
+            #[cfg(feature = "chrono")]
+            #[allow(clippy::cast_possible_truncation)]
+            Value::Date(v) => serializer.serialize_newtype_struct(
+                crate::date::CHRONO_DATE_TIME_INTERNAL_NAME,
+                &(*v as i64),
+            ),
+            #[cfg(not(feature = "chrono"))]
+            Value::Date(v) => serializer.serialize_f64(*v),
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Value {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any JS value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Number(v as f64))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Number(v as f64))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<Value, E> {
+        Ok(Value::BigInt(v))
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Value, E> {
+        i128::try_from(v)
+            .map(Value::BigInt)
+            .map_err(|_| E::custom("u128 out of the range of i128"))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Number(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Bytes(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        de::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element()? {
+            elements.push(element);
+        }
+        Ok(Value::Array(elements))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut properties = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(property) = map.next_entry()? {
+            properties.push(property);
+        }
+        Ok(Value::Object(properties))
+    }
+}
+
+/// Builds a `Value` like `ser::Serializer` builds a handle
+struct ValueSerializer;
+
+/// Collects the elements of a seq, tuple or tuple variant
+#[doc(hidden)]
+pub struct Elements {
+    variant: Option<&'static str>,
+    elements: Vec<Value>,
+}
+
+/// Collects the entries of a map, struct or struct variant
+#[doc(hidden)]
+pub struct Properties {
+    variant: Option<&'static str>,
+    entries: Vec<(String, Value)>,
+    key: Option<String>,
+}
+
+/// `{ variant: value }` for the data of an enum variant
+fn variant(variant: Option<&'static str>, value: Value) -> Value {
+    match variant {
+        Some(variant) => Value::Object(vec![(variant.to_owned(), value)]),
+        None => value,
+    }
+}
+
+macro_rules! serialize_number {
+    ($($method:ident($type:ty),)*) => {
+        $(
+            #[allow(clippy::cast_lossless, clippy::cast_precision_loss)]
+            fn $method(self, v: $type) -> LibResult<Value> {
+                Ok(Value::Number(v as f64))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = LibError;
+
+    type SerializeSeq = Elements;
+    type SerializeTuple = Elements;
+    type SerializeTupleStruct = Elements;
+    type SerializeTupleVariant = Elements;
+    type SerializeMap = Properties;
+    type SerializeStruct = Properties;
+    type SerializeStructVariant = Properties;
+
+    serialize_number! {
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+    }
+
+    fn serialize_bool(self, v: bool) -> LibResult<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_char(self, v: char) -> LibResult<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> LibResult<Value> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> LibResult<Value> {
+        Ok(Value::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> LibResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> LibResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> LibResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> LibResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> LibResult<Value> {
+        Ok(Value::String(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        #[allow(unused)] name: &'static str,
+        value: &T,
+    ) -> LibResult<Value> {
+        #[cfg(feature = "chrono")]
+        if name == crate::date::CHRONO_DATE_TIME_INTERNAL_NAME {
+            return match value.serialize(self)? {
+                Value::Number(millis) => Ok(Value::Date(millis)),
+                value => Ok(value),
+            };
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> LibResult<Value> {
+        let value = value.serialize(self)?;
+        Ok(self::variant(Some(variant), value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> LibResult<Elements> {
+        Ok(Elements {
+            variant: None,
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> LibResult<Elements> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> LibResult<Elements> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> LibResult<Elements> {
+        Ok(Elements {
+            variant: Some(variant),
+            elements: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> LibResult<Properties> {
+        Ok(Properties {
+            variant: None,
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> LibResult<Properties> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> LibResult<Properties> {
+        Ok(Properties {
+            variant: Some(variant),
+            entries: Vec::with_capacity(len),
+            key: None,
+        })
+    }
+}
+
+impl Elements {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<()> {
+        self.elements.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        variant(self.variant, Value::Array(self.elements))
+    }
+}
+
+impl ser::SerializeSeq for Elements {
+    type Ok = Value;
+    type Error = LibError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> LibResult<Value> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for Elements {
+    type Ok = Value;
+    type Error = LibError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> LibResult<Value> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for Elements {
+    type Ok = Value;
+    type Error = LibError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> LibResult<Value> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for Elements {
+    type Ok = Value;
+    type Error = LibError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> LibResult<Value> {
+        Ok(self.finish())
+    }
+}
+
+impl Properties {
+    fn push<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> LibResult<()> {
+        self.entries.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        variant(self.variant, Value::Object(self.entries))
+    }
+}
+
+impl ser::SerializeMap for Properties {
+    type Ok = Value;
+    type Error = LibError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> LibResult<()> {
+        self.key = Some(match key.serialize(MapKeySerializer)? {
+            MapKey::Name(name) => name,
+            MapKey::Number(v) => number_to_string(v),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> LibResult<()> {
+        let key = self.key.take().ok_or_else(|| {
+            <LibError as ser::Error>::custom("map value serialized before its key")
+        })?;
+        self.push(key, value)
+    }
+
+    fn end(self) -> LibResult<Value> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStruct for Properties {
+    type Ok = Value;
+    type Error = LibError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> LibResult<()> {
+        self.push(key.to_owned(), value)
+    }
+
+    fn end(self) -> LibResult<Value> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for Properties {
+    type Ok = Value;
+    type Error = LibError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> LibResult<()> {
+        self.push(key.to_owned(), value)
+    }
+
+    fn end(self) -> LibResult<Value> {
+        Ok(self.finish())
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> LibResult<V::Value> {
+                match self {
+                    // integers have no negative zero, read `-0` as `0`
+                    Value::Number(v) if v == 0.0 => visitor.visit_u64(0),
+                    value => value.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+/// Deserializes like `de::Deserializer` with `Coercion::Standard`
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = LibError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> LibResult<V::Value> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(v) => visitor.visit_bool(v),
+            Value::Number(v) | Value::Date(v) => visit_number(v, visitor),
+            Value::BigInt(v) => match i64::try_from(v) {
+                Ok(v) => visitor.visit_i64(v),
+                Err(_) => visitor.visit_i128(v),
+            },
+            Value::String(v) => visitor.visit_string(v),
+            Value::Bytes(v) => visitor.visit_byte_buf(v),
+            Value::Array(elements) => visitor.visit_seq(Elements::access(elements)),
+            Value::Object(properties) => visitor.visit_map(Properties::access(properties)),
+        }
+    }
+
+    deserialize_integer! {
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_i128,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_u128,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> LibResult<V::Value> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        #[allow(unused)] name: &'static str,
+        visitor: V,
+    ) -> LibResult<V::Value> {
+        // a `date::JsDate` field reads the `Date` as its timestamp
+        #[cfg(feature = "chrono")]
+        if name == crate::date::CHRONO_DATE_TIME_INTERNAL_NAME {
+            return self.deserialize_i64(visitor);
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> LibResult<V::Value> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(Variant {
+                variant,
+                value: VariantValue(None),
+            }),
+            Value::Object(properties) if properties.len() == 1 => {
+                let (variant, value) = properties.into_iter().next().expect("one property");
+                visitor.visit_enum(Variant {
+                    variant,
+                    value: VariantValue(Some(value)),
+                })
+            }
+            Value::Object(properties) => Err(LibError::InvalidKeyType {
+                key: format!("object key with {} properties", properties.len()),
+            }),
+            value => Err(de::Error::invalid_type(value.unexpected(), &"enum")),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> LibResult<V::Value> {
+        match self {
+            Value::Bytes(v) => visitor.visit_byte_buf(v),
+            value => Err(de::Error::invalid_type(value.unexpected(), &"a Buffer")),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> LibResult<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> LibResult<V::Value> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool f32 f64 char str string unit unit_struct seq tuple tuple_struct map
+        struct identifier
+    }
+}
+
+impl IntoDeserializer<'_, LibError> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Reads the elements of an `Array`
+struct ElementsAccess(vec::IntoIter<Value>);
+
+impl Elements {
+    fn access(elements: Vec<Value>) -> ElementsAccess {
+        ElementsAccess(elements.into_iter())
+    }
+}
+
+impl<'de> SeqAccess<'de> for ElementsAccess {
+    type Error = LibError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> LibResult<Option<T::Value>> {
+        self.0
+            .next()
+            .map(|element| seed.deserialize(element))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// Reads the properties of an `Object`, keys are parsed back like the
+/// property names of a JS object
+struct PropertiesAccess {
+    properties: vec::IntoIter<(String, Value)>,
+    value: Option<Value>,
+}
+
+impl Properties {
+    fn access(properties: Vec<(String, Value)>) -> PropertiesAccess {
+        PropertiesAccess {
+            properties: properties.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for PropertiesAccess {
+    type Error = LibError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> LibResult<Option<K::Value>> {
+        let Some((key, value)) = self.properties.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        seed.deserialize(MapKeyDeserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> LibResult<V::Value> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| <LibError as de::Error>::custom("map value read before its key"))?;
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.properties.len())
+    }
+}
+
+/// An enum variant, a string for a unit variant or a single property object
+struct Variant {
+    variant: String,
+    value: VariantValue,
+}
+
+/// The data of a variant, `None` for a unit variant written as a string
+struct VariantValue(Option<Value>);
+
+impl<'de> EnumAccess<'de> for Variant {
+    type Error = LibError;
+    type Variant = VariantValue;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> LibResult<(V::Value, VariantValue)> {
+        let variant = seed.deserialize(IntoDeserializer::<LibError>::into_deserializer(
+            self.variant,
+        ))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantValue {
+    type Error = LibError;
+
+    fn unit_variant(self) -> LibResult<()> {
+        match self.0 {
+            Some(value) => de::Deserialize::deserialize(value),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> LibResult<T::Value> {
+        match self.0 {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> LibResult<V::Value> {
+        match self.0 {
+            Some(Value::Array(elements)) => visitor.visit_seq(Elements::access(elements)),
+            Some(value) => Err(de::Error::invalid_type(
+                value.unexpected(),
+                &"tuple variant",
+            )),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> LibResult<V::Value> {
+        match self.0 {
+            Some(Value::Object(properties)) => visitor.visit_map(Properties::access(properties)),
+            Some(value) => Err(de::Error::invalid_type(
+                value.unexpected(),
+                &"struct variant",
+            )),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect { w: u32, h: u32 },
+        Line(i32, i32),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Drawing {
+        name: String,
+        shapes: Vec<Shape>,
+        layers: BTreeMap<u8, Option<bool>>,
+        #[serde(with = "serde_bytes_like")]
+        data: Vec<u8>,
+    }
+
+    mod serde_bytes_like {
+        pub fn serialize<S: serde::Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_bytes(v)
+        }
+
+        pub fn deserialize<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+            serde::Deserialize::deserialize(d).map(|v: super::Value| match v {
+                super::Value::Bytes(v) => v,
+                _ => Vec::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_from_serialize() {
+        let drawing = Drawing {
+            name: "plan".into(),
+            shapes: vec![
+                Shape::Empty,
+                Shape::Circle(1.5),
+                Shape::Rect { w: 2, h: 3 },
+                Shape::Line(-1, 1),
+            ],
+            layers: BTreeMap::from([(1, Some(true)), (2, None)]),
+            data: vec![1, 2],
+        };
+        let value = Value::from_serialize(&drawing).unwrap();
+        let object = |properties: Vec<(&str, Value)>| {
+            Value::Object(
+                properties
+                    .into_iter()
+                    .map(|(key, value)| (key.to_owned(), value))
+                    .collect(),
+            )
+        };
+        assert_eq!(
+            value,
+            object(vec![
+                ("name", Value::String("plan".into())),
+                (
+                    "shapes",
+                    Value::Array(vec![
+                        Value::String("Empty".into()),
+                        object(vec![("Circle", Value::Number(1.5))]),
+                        object(vec![(
+                            "Rect",
+                            object(vec![("w", Value::Number(2.0)), ("h", Value::Number(3.0))])
+                        )]),
+                        object(vec![(
+                            "Line",
+                            Value::Array(vec![Value::Number(-1.0), Value::Number(1.0)])
+                        )]),
+                    ])
+                ),
+                (
+                    "layers",
+                    object(vec![("1", Value::Bool(true)), ("2", Value::Null)])
+                ),
+                ("data", Value::Bytes(vec![1, 2])),
+            ])
+        );
+        assert_eq!(value.deserialize_into::<Drawing>().unwrap(), drawing);
+    }
+
+    #[test]
+    fn test_deserialize_into() {
+        assert_eq!(Value::Number(-0.0).deserialize_into::<u8>().unwrap(), 0);
+        assert_eq!(Value::Number(7.0).deserialize_into::<i64>().unwrap(), 7);
+        assert!(Value::Number(1.5).deserialize_into::<u32>().is_err());
+        assert_eq!(Value::BigInt(-3).deserialize_into::<i32>().unwrap(), -3);
+        assert_eq!(Value::Date(1000.0).deserialize_into::<u64>().unwrap(), 1000);
+        assert_eq!(Value::Null.deserialize_into::<Option<u8>>().unwrap(), None);
+        assert!(Value::String("Square".into())
+            .deserialize_into::<Shape>()
+            .is_err());
+        assert_eq!(
+            Value::from_serialize(&BTreeMap::from([(vec![1], "a")]))
+                .unwrap_err()
+                .to_string(),
+            "Unsupported map key type: 'seq', expected a string, number, bool or char"
+        );
+    }
+
+    /// Writes its pairs as a map, since `f64` keys are not `Ord` or `Hash`
+    struct FloatKeys(&'static [(f64, u8)]);
+
+    impl Serialize for FloatKeys {
+        fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+        }
+    }
+
+    #[test]
+    fn test_float_keys() {
+        let value = Value::from_serialize(&FloatKeys(&[
+            (1.0, 0),
+            (-0.0, 1),
+            (0.5, 2),
+            (1e21, 3),
+            (1e-7, 4),
+            (f64::NAN, 5),
+            (f64::NEG_INFINITY, 6),
+        ]))
+        .unwrap();
+        let Value::Object(entries) = value else {
+            panic!("expected an object, got {value:?}");
+        };
+        let keys: Vec<&str> = entries.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["1", "0", "0.5", "1e+21", "1e-7", "NaN", "-Infinity"]);
+    }
+}
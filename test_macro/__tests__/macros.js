@@ -25,7 +25,7 @@ describe('macro functions', () => {
         native.expect_buffer_only(new Buffer('000011110000', 'hex'))
         expect(() => {
           native.expect_buffer_only([1, 2, 3, 4])
-        }).toThrow(/failed downcast to Buffer/)
+        }).toThrow(/failed to downcast any to Buffer/)
 
         native.expect_array([0,0,0,0])
     })
@@ -41,4 +41,730 @@ describe('macro functions', () => {
             expect(native.maybe_say_hello(null)).toBe(null);
         })
     })
+
+    describe("map keys", () => {
+        it("round trips integer keys through property names", () => {
+            expect(native.double_scores({ 1: 10, 42: 7 })).toEqual({ 1: 20, 42: 14 });
+            expect(native.double_scores({})).toEqual({});
+        })
+
+        it("throws on keys that do not parse", () => {
+            expect(() => native.double_scores({ one: 10 })).toThrow(/argument 'scores' \(#0\): invalid type: string "one", expected u32/);
+            expect(() => native.double_scores({ "-1": 10 })).toThrow(/argument 'scores' \(#0\): .*expected u32/);
+        })
+
+        it("throws on composite keys", () => {
+            expect(() => native.grid()).toThrow(TypeError);
+            expect(() => native.grid()).toThrow("unsupported map key type: tuple");
+        })
+    })
+
+    describe("Coercion::Lenient", () => {
+        const form = { age: 32, balance: -42, ratio: 0.5, subscribed: true, zip: "12345", grade: "A" };
+
+        it("reads values of the target type as they are", () => {
+            expect(native.parse_form(form)).toEqual(form);
+        })
+
+        it("coerces strings, numbers and bools like JS", () => {
+            expect(native.parse_form({ age: "32", balance: " -42 ", ratio: "5e-1", subscribed: "true", zip: 12345, grade: 7 }))
+                .toEqual({ age: 32, balance: -42, ratio: 0.5, subscribed: true, zip: "12345", grade: "7" });
+            expect(native.parse_form({ ...form, zip: false }).zip).toBe("false");
+        })
+
+        it("reads the form encodings of bools", () => {
+            expect(native.parse_form({ ...form, subscribed: "1" }).subscribed).toBe(true);
+            expect(native.parse_form({ ...form, subscribed: "0" }).subscribed).toBe(false);
+            expect(native.parse_form({ ...form, subscribed: 1 }).subscribed).toBe(true);
+            expect(native.parse_form({ ...form, subscribed: "false" }).subscribed).toBe(false);
+        })
+
+        it("throws on values without a coercion", () => {
+            expect(() => native.parse_form({ ...form, age: "abc" })).toThrow('unable to coerce: expected u8, found "abc"');
+            expect(() => native.parse_form({ ...form, age: "256" })).toThrow('unable to coerce: expected u8, found "256"');
+            expect(() => native.parse_form({ ...form, balance: "1.5" })).toThrow('unable to coerce: expected i64, found "1.5"');
+            expect(() => native.parse_form({ ...form, ratio: "Infinity" })).toThrow('unable to coerce: expected f64, found "Infinity"');
+            expect(() => native.parse_form({ ...form, subscribed: "yes" })).toThrow('unable to coerce: expected bool, found "yes"');
+            expect(() => native.parse_form({ ...form, subscribed: 2 })).toThrow("unable to coerce: expected bool, found 2");
+            expect(() => native.parse_form({ ...form, grade: 10 })).toThrow("unable to coerce: expected char, found 10");
+        })
+    })
+
+    describe("Coercion::Strict", () => {
+        it("reads values of exactly the target type", () => {
+            expect(native.parse_strict_id(42)).toBe(42);
+            expect(native.parse_strict_id(2 ** 32 - 1)).toBe(2 ** 32 - 1);
+        })
+
+        it("names the expected and the found type", () => {
+            expect(() => native.parse_strict_id("1")).toThrow(TypeError);
+            expect(() => native.parse_strict_id("1")).toThrow('unable to coerce: expected u32, found string');
+            expect(() => native.parse_strict_id(true)).toThrow("unable to coerce: expected u32, found boolean");
+            expect(() => native.parse_strict_id(null)).toThrow("unable to coerce: expected u32, found null");
+            expect(() => native.parse_strict_id([1])).toThrow("unable to coerce: expected u32, found array");
+        })
+
+        it("names numbers that are not whole, safe or in range", () => {
+            expect(() => native.parse_strict_id(1.5)).toThrow("unable to coerce: expected u32, found 1.5");
+            expect(() => native.parse_strict_id(2 ** 53)).toThrow("unable to coerce: expected u32, found 9007199254740992");
+            expect(() => native.parse_strict_id(2 ** 32)).toThrow("unable to coerce: expected u32, found 4294967296");
+            expect(() => native.parse_strict_id(-1)).toThrow("unable to coerce: expected u32, found -1");
+            expect(() => native.parse_strict_id(NaN)).toThrow("unable to coerce: expected u32, found NaN");
+        })
+    })
+
+    describe("Result", () => {
+        it("Ok is returned", () => {
+            expect(native.parse_age("32")).toBe(32);
+            expect(native.request("", false)).toBe("done");
+            expect(native.check_status(200)).toBe(null);
+        })
+
+        it("Err is thrown with its Display message", () => {
+            expect(() => native.parse_age("old")).toThrow(Error);
+            expect(() => native.parse_age("old")).toThrow("invalid digit found in string");
+        })
+
+        it("Serialize errors keep their fields", () => {
+            let error;
+            try {
+                native.request("E_TIMEOUT", true);
+            } catch (e) {
+                error = e;
+            }
+            expect(error).toBeInstanceOf(Error);
+            expect(error.message).toBe("request failed with E_TIMEOUT");
+            expect(error.code).toBe("E_TIMEOUT");
+            expect(error.retry).toBe(true);
+        })
+
+        it("other serialized errors are set as data", () => {
+            let error;
+            try {
+                native.check_status(404);
+            } catch (e) {
+                error = e;
+            }
+            expect(error.message).toBe("status 404");
+            expect(error.data).toBe(404);
+        })
+
+        it("errors of this crate are thrown like or_throw", () => {
+            expect(native.to_char("x")).toBe("x");
+            expect(() => native.to_char("xy")).toThrow("string too long for char, length: 2");
+        })
+    })
+
+    describe("panics", () => {
+        it("are thrown as an Error naming the function", () => {
+            expect(() => native.explode("boom")).toThrow(Error);
+            expect(() => native.explode("boom")).toThrow(/^'explode' panicked: boom$/);
+            expect(() => native.explode_static()).toThrow(/^'explode_static' panicked: static message$/);
+        })
+
+        it("leave the module usable", () => {
+            expect(() => native.explode("boom")).toThrow();
+            expect(native.say_hello("again")).toBe("Hello, again!");
+        })
+    })
+
+    describe("async fn", () => {
+        it("resolves a Promise", async () => {
+            const promise = native.delayed(7, 10);
+            expect(promise).toBeInstanceOf(Promise);
+            expect(await promise).toBe(7);
+        })
+
+        it("runs many futures at once", async () => {
+            const values = Array.from({ length: 64 }, (_, i) => i);
+            const start = Date.now();
+            const results = await Promise.all(values.map(i => native.delayed(i, 50)));
+            expect(results).toEqual(values);
+            // the futures wait together, not one after another
+            expect(Date.now() - start).toBeLessThan(1000);
+        })
+
+        it("rejects with the Err", async () => {
+            expect(await native.delayed_parse("32")).toBe(32);
+            await expect(native.delayed_parse("old")).rejects.toThrow("invalid digit found in string");
+        })
+
+        it("rejects on panic", async () => {
+            await expect(native.delayed_explode()).rejects.toThrow(/^'delayed_explode' panicked: async boom$/);
+        })
+
+        it("throws argument errors synchronously", () => {
+            expect(() => native.delayed("seven", 1)).toThrow(TypeError);
+        })
+    })
+
+    describe("#[blocking]", () => {
+        it("resolves a Promise", async () => {
+            const promise = native.blocking_fibonacci(10);
+            expect(promise).toBeInstanceOf(Promise);
+            expect(await promise).toBe(55);
+        })
+
+        it("rejects with the structured Err", async () => {
+            await expect(native.blocking_request("E_BUSY")).rejects.toMatchObject({
+                message: "request failed with E_BUSY",
+                code: "E_BUSY",
+                retry: false,
+            });
+        })
+
+        it("rejects on panic", async () => {
+            await expect(native.blocking_explode()).rejects.toThrow(/^'blocking_explode' panicked: blocking boom$/);
+        })
+    })
+
+    describe("FunctionContext and handles", () => {
+        it("passes handles through and returns a JsResult as is", () => {
+            let calls = 0;
+            expect(native.call_twice(() => ++calls)).toBe(2);
+            expect(calls).toBe(2);
+
+            const object = { a: 1 };
+            expect(native.identity(object)).toBe(object);
+            expect(native.own_keys({ a: 1, b: 2 })).toEqual(["a", "b"]);
+        })
+
+        it("mixes handles and deserialized params", () => {
+            expect(native.labelled("answer", 42)).toBe("answer: 42");
+        })
+
+        it("rethrows exceptions of the JS engine", () => {
+            expect(() => native.call_twice(() => { throw new RangeError("from JS"); })).toThrow(RangeError);
+        })
+
+        it("names the param of a handle of the wrong type", () => {
+            expect(() => native.own_keys(1)).toThrow(/^argument 'object' \(#0\): failed to downcast/);
+            expect(() => native.labelled("answer", "42")).toThrow(/^argument 'value' \(#1\): /);
+        })
+    })
+
+    describe("#![register]", () => {
+        it("combines blocks with hand-written exports", () => {
+            expect(native.add(1, 2)).toBe(3);
+            expect(native.version()).toBe("0.1.0");
+            expect(native.say_hello("World")).toBe("Hello, World!");
+        })
+    })
+
+    describe("#[js_name], namespaces and #[cfg]", () => {
+        it("#[js_name] renames the export", () => {
+            expect(native.sayGoodbye("Bob")).toBe("Goodbye, Bob!");
+            expect(native.say_goodbye).toBeUndefined();
+            expect(native.sayGoodbye.name).toBe("sayGoodbye");
+        })
+
+        it("mod blocks are exported as objects", () => {
+            expect(native.text.shout("hi")).toBe("HI");
+            expect(native.text.isEmpty("")).toBe(true);
+            expect(native.text.is_empty).toBeUndefined();
+            expect(native.nested.inner.depth()).toBe(2);
+            expect(native.nested_namespace).toBeUndefined();
+        })
+
+        it("#[cfg] removes the export", () => {
+            expect("never_exported" in native).toBe(false);
+            expect("never_exported" in native.text).toBe(false);
+            const expected = process.platform === "win32" ? "\r\n" : "\n";
+            expect(native.text.line_ending()).toBe(expected);
+        })
+    })
+
+    describe("arity and argument errors", () => {
+        it("throws on too many arguments", () => {
+            expect(() => native.say_hello("World", "again")).toThrow(TypeError);
+            expect(() => native.say_hello("World", "again"))
+                .toThrow(/^'say_hello' expected at most 1 arguments but got 2$/);
+        })
+
+        it("lets functions taking the context read extra arguments", () => {
+            expect(native.count_arguments("args")).toBe("args: 1");
+            expect(native.count_arguments("args", 2, 3)).toBe("args: 3");
+            expect(() => native.count_arguments()).toThrow(/missing 'label'/);
+        })
+
+        it("names Buffer params that are not a Buffer", () => {
+            expect(() => native.expect_buffer_only([1, 2, 3, 4]))
+                .toThrow(/^argument '_buff' \(#0\): failed to downcast any to Buffer$/);
+            expect(() => native.expect_buffer_only("0000")).toThrow(TypeError);
+        })
+
+        it("names the first missing argument", () => {
+            expect(() => native.say_hello())
+                .toThrow(/^'say_hello' expected at least 1 arguments but got 0, missing 'name'$/);
+            expect(() => native.request("E_BUSY", undefined))
+                .toThrow(/^'request' missing argument 'retry' \(#1\)$/);
+        })
+
+        it("Option arguments may be missing", () => {
+            expect(native.maybe_say_hello()).toBe(null);
+            expect(native.maybe_say_hello(undefined)).toBe(null);
+        })
+
+        it("names the argument that failed to deserialize", () => {
+            expect(() => native.greet({ name: "Bob", age: "old" })).toThrow(TypeError);
+            expect(() => native.greet({ name: "Bob", age: "old" })).toThrow(/^argument 'user' \(#0\): /);
+            expect(() => native.request("E_BUSY", "yes")).toThrow(/^argument 'retry' \(#1\): /);
+        })
+    })
+
+    describe("#[options] and defaults", () => {
+        it("reads the params from an object", () => {
+            expect(native.listen({ host: "example.com", port: 8080 })).toBe("example.com:8080");
+            expect(native.connect({ host: "example.com", secure: true })).toBe("https://example.com:80");
+        })
+
+        it("uses the defaults of missing and undefined params", () => {
+            expect(native.listen({ port: 8080 })).toBe("localhost:8080");
+            expect(native.listen({ host: undefined })).toBe("localhost:80");
+            expect(native.listen({})).toBe("localhost:80");
+            expect(native.listen()).toBe("localhost:80");
+            expect(native.repeat("ab")).toBe("abab");
+            expect(native.repeat("ab", undefined)).toBe("abab");
+            expect(native.repeat("ab", 3)).toBe("ababab");
+        })
+
+        it("ignores unknown options", () => {
+            expect(native.listen({ port: 1, verbose: true })).toBe("localhost:1");
+        })
+
+        it("names missing and invalid options", () => {
+            expect(() => native.connect({})).toThrow(/^'connect' missing option 'host'$/);
+            expect(() => native.connect()).toThrow(/^'connect' missing option 'host'$/);
+            expect(() => native.listen({ port: "http" })).toThrow(TypeError);
+            expect(() => native.listen({ port: "http" })).toThrow(/^option 'port': /);
+        })
+
+        it("expects a single options object", () => {
+            expect(() => native.listen(8080)).toThrow(/^'listen' expected an options object$/);
+            expect(() => native.listen({}, {})).toThrow(/^'listen' expected at most 1 arguments but got 2$/);
+        })
+    })
+
+    describe("#[rest]", () => {
+        it("collects the remaining arguments", () => {
+            expect(native.sum()).toBe(0);
+            expect(native.sum(1, 2, 3)).toBe(6);
+            expect(native.sum(...Array(100).fill(1))).toBe(100);
+            expect(native.join("-", "a", "b", "c")).toBe("a-b-c");
+            expect(native.join("-")).toBe("");
+        })
+
+        it("names the argument that failed to deserialize", () => {
+            expect(() => native.sum(1, "two", 3)).toThrow(TypeError);
+            expect(() => native.sum(1, "two", 3)).toThrow(/^argument 'values' \(#1\): /);
+            expect(() => native.join("-", "a", 2)).toThrow(/^argument 'parts' \(#2\): /);
+        })
+
+        it("still requires the params before it", () => {
+            expect(() => native.join())
+                .toThrow(/^'join' expected at least 1 arguments but got 0, missing 'separator'$/);
+        })
+
+        it("is read as an array with #[options]", () => {
+            expect(native.tag({ name: "x", tags: ["a", "b"] })).toBe("x [a, b]");
+            expect(native.tag({ name: "x" })).toBe("x []");
+            expect(() => native.tag({ name: "x", tags: "a" })).toThrow(/^option 'tags': /);
+        })
+    })
+
+    describe("classes", () => {
+        it("constructs instances of the class", () => {
+            const counter = new native.Counter(1);
+            expect(counter).toBeInstanceOf(native.Counter);
+            expect(counter).not.toBeInstanceOf(native.Stack);
+            expect(native.Counter.name).toBe("Counter");
+            expect(Object.keys(counter)).toEqual([]);
+        })
+
+        it("keeps the value of each instance", () => {
+            const a = new native.Counter(1);
+            const b = new native.Counter(10);
+            expect(a.increment()).toBe(2);
+            expect(a.add(3)).toBe(5);
+            expect(b.increment()).toBe(11);
+            expect(a.get()).toBe(5);
+            expect(b.get()).toBe(11);
+
+            const stack = new native.Stack();
+            expect(stack.peek()).toBe(null);
+            expect(stack.push("a")).toBe(1);
+            expect(stack.peek()).toBe("a");
+        })
+
+        it("sets methods on the prototype and other functions on the class", () => {
+            expect(typeof native.Counter.prototype.increment).toBe("function");
+            expect(native.Counter.prototype.describe).toBeUndefined();
+            expect(native.Counter.describe(3)).toBe("counted to 3");
+        })
+
+        it("can be extended", () => {
+            class Doubler extends native.Counter {
+                double() { return this.add(this.get()); }
+            }
+            const doubler = new Doubler(2);
+            expect(doubler).toBeInstanceOf(native.Counter);
+            expect(doubler.double()).toBe(4);
+        })
+
+        it("throws when the constructor is called without new", () => {
+            expect(() => native.Counter(1)).toThrow(TypeError);
+            expect(() => native.Counter(1))
+                .toThrow(/^class constructor 'Counter' cannot be invoked without 'new'$/);
+        })
+
+        it("names the arguments of constructors and methods", () => {
+            expect(() => new native.Counter("one")).toThrow(/^argument 'start' \(#0\): /);
+            expect(() => new native.Counter(1).add("one")).toThrow(/^argument 'amount' \(#0\): /);
+            expect(() => new native.Counter()).toThrow(/^'Counter' expected at least 1 arguments but got 0/);
+        })
+
+        it("throws on methods called with an incompatible receiver", () => {
+            const counter = new native.Counter(1);
+            const stack = new native.Stack();
+            for (const receiver of [{}, stack, 1, undefined, Object.create(native.Counter.prototype)]) {
+                expect(() => counter.get.call(receiver)).toThrow(TypeError);
+                expect(() => counter.get.call(receiver))
+                    .toThrow(/^'Counter.get' called on an incompatible receiver$/);
+            }
+            expect(() => stack.push.call(counter, "a"))
+                .toThrow(/^'Stack.push' called on an incompatible receiver$/);
+        })
+    })
+
+    describe("const and #[frozen]", () => {
+        it("exports serialized values", () => {
+            expect(native.VERSION).toBe("0.1.0");
+            expect(native.ANSWER).toBe(42);
+            expect(native.PORTS).toEqual([80, 443]);
+            expect(native.CONFIG).toEqual({
+                name: "app",
+                limits: { connections: 64, timeout: 30 },
+                tags: ["a", "b"],
+            });
+            expect(native.DEFAULTS.limits.timeout).toBe(null);
+        })
+
+        it("leaves values without #[frozen] mutable", () => {
+            expect(Object.isFrozen(native.DEFAULTS)).toBe(false);
+            native.DEFAULTS.tags.push("pushed");
+            expect(native.DEFAULTS.tags).toEqual(["mutable", "pushed"]);
+        })
+
+        it("deeply freezes #[frozen] values", () => {
+            expect(Object.isFrozen(native.PORTS)).toBe(true);
+            expect(Object.isFrozen(native.CONFIG)).toBe(true);
+            expect(Object.isFrozen(native.CONFIG.limits)).toBe(true);
+            expect(Object.isFrozen(native.CONFIG.tags)).toBe(true);
+            expect(() => native.PORTS.push(8080)).toThrow(TypeError);
+            expect(() => { "use strict"; native.CONFIG.limits.connections = 1; }).toThrow(TypeError);
+            expect(native.CONFIG.limits.connections).toBe(64);
+        })
+    })
+
+    describe("call_with", () => {
+        it("passes the elements of a tuple as arguments", () => {
+            let seen;
+            expect(native.call_with_tuple((...args) => { seen = args; return "done"; })).toBe("done");
+            expect(seen).toEqual(["Bob", 32, true]);
+        })
+
+        it("passes the fields of a struct as arguments in order", () => {
+            let seen;
+            expect(native.call_with_struct((...args) => { seen = args; return args[1] * 2; })).toBe(54);
+            expect(seen).toEqual(["Alice", 27]);
+        })
+
+        it("passes no arguments for () and calls with the receiver", () => {
+            let seen;
+            const receiver = { name: "receiver" };
+            function name(...args) {
+                seen = args;
+                return this.name;
+            }
+            expect(native.call_with_unit(name, receiver)).toBe("receiver");
+            expect(seen).toEqual([]);
+            expect(native.call_with_unit(() => undefined, receiver)).toBe(null);
+        })
+
+        it("throws on arguments that are not a tuple or a struct", () => {
+            expect(() => native.call_with_number(() => undefined)).toThrow(/unable to serialize/);
+        })
+
+        it("rethrows exceptions of the function", () => {
+            const error = new RangeError("out of range");
+            expect(() => native.call_with_tuple(() => { throw error; })).toThrow(error);
+        })
+
+        it("throws when the returned value does not deserialize", () => {
+            expect(() => native.call_with_struct(() => "many")).toThrow(/invalid type: string "many", expected u32/);
+        })
+    })
+
+    describe("JsCallback", () => {
+        it("calls the function with serialized arguments", () => {
+            const calls = [];
+            expect(native.apply_twice(3, (x) => { calls.push(x); return x * 2; })).toBe(12);
+            expect(calls).toEqual([3, 6]);
+        })
+
+        it("rethrows exceptions of the function", () => {
+            expect(() => native.apply_twice(1, () => { throw new RangeError("from JS"); })).toThrow(RangeError);
+        })
+
+        it("throws when the returned value does not deserialize", () => {
+            expect(() => native.apply_twice(1, () => "two")).toThrow(TypeError);
+            expect(() => native.apply_twice(1, () => "two")).toThrow(/^unable to deserialize: invalid type: string "two"/);
+        })
+
+        it("names the param of a callback that is not a function", () => {
+            expect(() => native.apply_twice(1, 2)).toThrow(TypeError);
+            expect(() => native.apply_twice(1, 2)).toThrow(/^argument 'f' \(#1\): failed to downcast/);
+        })
+
+        it("is called from another thread with call_blocking", async () => {
+            const seen = [];
+            const done = await native.process(["a", "b", "c"], (done, total) => {
+                seen.push([done, total]);
+                return true;
+            });
+            expect(done).toBe(3);
+            expect(seen).toEqual([[0, 3], [1, 3], [2, 3]]);
+        })
+
+        it("returns the value of the function to the other thread", async () => {
+            expect(await native.process(["a", "b", "c"], (done) => done < 1)).toBe(1);
+        })
+
+        it("reports exceptions to the other thread", async () => {
+            await expect(native.process(["a"], () => { throw new Error("cancelled"); }))
+                .rejects.toThrow(/cancelled/);
+        })
+
+        it("sends calls from another thread without waiting", (done) => {
+            // the callback does not keep the event loop alive
+            const timeout = setTimeout(() => done(new Error("not called")), 1000);
+            const received = [];
+            native.notify_later(["a", "b"], (message, i) => {
+                received.push([message, i]);
+                if (received.length === 2) {
+                    clearTimeout(timeout);
+                    try {
+                        expect(received).toEqual([["a", 0], ["b", 1]]);
+                        done();
+                    } catch (err) {
+                        done(err);
+                    }
+                }
+            });
+        })
+    })
+
+    describe("Emitter", () => {
+        it("delivers events in order before flush returns", async () => {
+            const seen = [];
+            expect(await native.emit_counts(3, 1024, (event, n) => { seen.push([event, n]); })).toBe(3);
+            expect(seen).toEqual([["count", 0], ["count", 1], ["count", 2]]);
+        })
+
+        it("emit waits while the queue is full", async () => {
+            const seen = [];
+            expect(await native.emit_counts(20, 1, (event, n) => { seen.push(n); })).toBe(20);
+            expect(seen).toEqual([...Array(20).keys()]);
+        })
+
+        it("calls emit on an EventEmitter", async () => {
+            const { EventEmitter } = require("events");
+            const events = new EventEmitter();
+            const seen = [];
+            events.on("count", (n) => seen.push(n));
+            expect(await native.emit_counts(3, 1, events)).toBe(3);
+            expect(seen).toEqual([0, 1, 2]);
+        })
+
+        it("try_emit throws when the queue is full", async () => {
+            const seen = [];
+            expect(() => native.try_emit_twice((event, n) => { seen.push([event, n]); }))
+                .toThrow(/^emitter queue full, capacity: 1$/);
+            // the queued event is still delivered
+            for (let i = 0; i < 100 && seen.length === 0; i++) {
+                await new Promise((resolve) => setTimeout(resolve, 5));
+            }
+            expect(seen).toEqual([["first", 1]]);
+        })
+
+        it("reports a listener that threw to the emitting thread", async () => {
+            await expect(native.emit_counts(3, 1, () => { throw new Error("listener failed"); }))
+                .rejects.toThrow(/listener failed/);
+        })
+
+        it("names the param that is not a function or an EventEmitter", () => {
+            expect(() => native.emit_counts(1, 1, {})).toThrow(TypeError);
+            expect(() => native.emit_counts(1, 1, {}))
+                .toThrow(/^argument 'events' \(#2\): expected a function or an EventEmitter$/);
+            expect(() => native.emit_counts(1, 1, 2))
+                .toThrow(/^argument 'events' \(#2\): expected a function or an EventEmitter$/);
+        })
+    })
+
+    describe("from_promise rejections", () => {
+        it("resolves with the fulfilled value", async () => {
+            expect(await native.rethrow_rejection(Promise.resolve(42))).toBe(42);
+        })
+
+        it("rethrows an Error with its message, name and properties", async () => {
+            const error = Object.assign(new RangeError("out of range"), { code: "E_RANGE", details: { max: 10 } });
+            const rethrown = await native.rethrow_rejection(Promise.reject(error)).catch((err) => err);
+            expect(rethrown).toBeInstanceOf(Error);
+            expect(rethrown).not.toBe(error);
+            expect(rethrown.message).toBe("out of range");
+            expect(rethrown.name).toBe("RangeError");
+            expect(rethrown.code).toBe("E_RANGE");
+            expect(rethrown.details).toEqual({ max: 10 });
+        })
+
+        it("rethrows other reasons as they were", async () => {
+            await expect(native.rethrow_rejection(Promise.reject({ status: 404 }))).rejects.toEqual({ status: 404 });
+            await expect(native.rethrow_rejection(Promise.reject("plain"))).rejects.toBe("plain");
+            await expect(native.rethrow_rejection(Promise.reject([1, 2]))).rejects.toEqual([1, 2]);
+        })
+
+        it("deserializes the reason", async () => {
+            const error = Object.assign(new Error("not found"), { status: 404 });
+            expect(await native.rejection_status(Promise.reject(error))).toBe("404 not found");
+            expect(await native.rejection_status(Promise.reject({ message: "gone", status: 410 }))).toBe("410 gone");
+            await expect(native.rejection_status(Promise.reject(new Error("no status")))).rejects.toThrow(/status/);
+        })
+
+        it("keeps the message of a reason that can not be read", async () => {
+            const cyclic = new Error("cyclic");
+            cyclic.self = cyclic;
+            await expect(native.rethrow_rejection(Promise.reject(cyclic))).rejects.toBe("cyclic");
+        })
+    })
+
+    describe("Value::from_js", () => {
+        const nest = (depth) => {
+            let value = 0;
+            for (let i = 0; i < depth; i++) {
+                value = i % 2 ? [value] : { value };
+            }
+            return value;
+        };
+
+        it("copies nested values", () => {
+            const value = { a: [1, "two", null, { b: true }], c: Buffer.from([1, 2]) };
+            expect(native.copy_value(value)).toEqual(value);
+            expect(native.copy_value(nest(128))).toEqual(nest(128));
+        })
+
+        it("reads a value shared by siblings twice", () => {
+            const shared = { x: 1 };
+            expect(native.copy_value([shared, { shared }])).toEqual([{ x: 1 }, { shared: { x: 1 } }]);
+        })
+
+        it("throws on cyclic values", () => {
+            const object = { a: {} };
+            object.a.parent = object;
+            expect(() => native.copy_value(object)).toThrow(TypeError);
+            expect(() => native.copy_value(object)).toThrow(/cyclic value/);
+            const array = [1];
+            array.push([array]);
+            expect(() => native.copy_value(array)).toThrow(/cyclic value/);
+        })
+
+        it("throws on values nested too deep", () => {
+            expect(() => native.copy_value(nest(129))).toThrow(/nested deeper than 128 levels/);
+            expect(() => native.copy_value(nest(100000))).toThrow(/nested deeper than 128 levels/);
+        })
+
+        it("throws on functions and symbols", () => {
+            expect(() => native.copy_value(() => 1)).toThrow(/a function can not be read/);
+            expect(() => native.copy_value({ f() {} })).toThrow(/a function can not be read/);
+            expect(() => native.copy_value([Symbol("s")])).toThrow(/a symbol can not be read/);
+        })
+
+        it("reads other objects as their own enumerable properties", () => {
+            class Point { constructor() { this.x = 1; } get y() { return 2; } }
+            expect(native.copy_value(new Point())).toEqual({ x: 1 });
+            expect(native.copy_value(new Map([["a", 1]]))).toEqual({});
+        })
+    })
+
+    describe("iterators", () => {
+        it("to_iterator works with for...of and spreading", () => {
+            const seen = [];
+            for (const n of native.count_to(3)) {
+                seen.push(n);
+            }
+            expect(seen).toEqual([1, 2, 3]);
+            expect([...native.count_to(2)]).toEqual([1, 2]);
+            expect(Array.from(native.count_to(0))).toEqual([]);
+        })
+
+        it("to_iterator stays done once exhausted", () => {
+            const iterator = native.count_to(1);
+            expect(iterator.next()).toEqual({ value: 1, done: false });
+            expect(iterator.next()).toEqual({ value: undefined, done: true });
+            expect(iterator.next()).toEqual({ value: undefined, done: true });
+        })
+
+        it("to_iterator drops the iterator when a loop exits early", () => {
+            for (const n of native.count_forever()) {
+                if (n === 3) {
+                    expect(native.is_dropped()).toBe(false);
+                    break;
+                }
+            }
+            expect(native.is_dropped()).toBe(true);
+        })
+
+        it("to_iterator throws when an item does not serialize", () => {
+            expect(() => native.unserializable_items().next()).toThrow(/not serializable/);
+        })
+
+        it("to_async_iterator works with for await...of", async () => {
+            const seen = [];
+            for await (const n of native.ticks(3)) {
+                seen.push(n);
+            }
+            expect(seen).toEqual([0, 1, 2]);
+        })
+
+        it("to_async_iterator settles concurrent calls in order", async () => {
+            const iterator = native.ticks(2);
+            const results = await Promise.all([iterator.next(), iterator.next(), iterator.next(), iterator.next()]);
+            expect(results).toEqual([
+                { value: 0, done: false },
+                { value: 1, done: false },
+                { value: undefined, done: true },
+                { value: undefined, done: true },
+            ]);
+        })
+
+        it("to_async_iterator drops the stream after return()", async () => {
+            for await (const n of native.ticks_forever()) {
+                if (n === 3) {
+                    break;
+                }
+            }
+            expect(await native.ticks_forever().return()).toEqual({ value: undefined, done: true });
+            // the stream is dropped on the executor
+            for (let i = 0; i < 100 && !native.is_dropped(); i++) {
+                await new Promise((resolve) => setTimeout(resolve, 5));
+            }
+            expect(native.is_dropped()).toBe(true);
+        })
+
+        it("to_async_iterator is done after return()", async () => {
+            const iterator = native.ticks(5);
+            expect(await iterator.next()).toEqual({ value: 0, done: false });
+            await iterator.return();
+            expect(await iterator.next()).toEqual({ value: undefined, done: true });
+        })
+    })
 });
@@ -0,0 +1,688 @@
+#![recursion_limit = "512"]
+
+use neon::prelude::*;
+use neon_serde::promise::Rejection;
+use neon_serde::{export, Coercion, Emitter, JsCallback};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Poll};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+struct User {
+    name: String,
+    age: u16,
+}
+
+/// Loosely typed input read with `Coercion::Lenient`
+#[derive(Deserialize, Serialize)]
+struct Form {
+    age: u8,
+    balance: i64,
+    ratio: f64,
+    subscribed: bool,
+    zip: String,
+    grade: char,
+}
+
+/// Passed to `call_with` as the arguments `(name, age)`
+#[derive(Serialize)]
+struct Person {
+    name: String,
+    age: u16,
+}
+
+/// Serialized onto the thrown `Error` as `code` and `retry`
+#[derive(Serialize)]
+struct AppError {
+    code: String,
+    retry: bool,
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "request failed with {}", self.code)
+    }
+}
+
+/// Not an object, serialized as `data`
+#[derive(Serialize)]
+struct Status(u16);
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "status {}", self.0)
+    }
+}
+
+/// Yields `0..len`, each item after a delay
+struct Ticks {
+    next: u32,
+    len: u32,
+    delay: Option<Delay>,
+}
+
+impl futures_core::Stream for Ticks {
+    type Item = u32;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Option<u32>> {
+        if self.next == self.len {
+            return Poll::Ready(None);
+        }
+        let delay = self.delay.get_or_insert_with(|| Delay::new(1));
+        if Pin::new(delay).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        self.delay = None;
+        self.next += 1;
+        Poll::Ready(Some(self.next - 1))
+    }
+}
+
+/// Set when a `Tracked` iterator or stream is dropped
+static DROPPED: AtomicBool = AtomicBool::new(false);
+
+/// Counts up forever, sets `DROPPED` when dropped
+struct Tracked(u32);
+
+impl Iterator for Tracked {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        self.0 += 1;
+        Some(self.0)
+    }
+}
+
+impl futures_core::Stream for Tracked {
+    type Item = u32;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut TaskContext) -> Poll<Option<u32>> {
+        Poll::Ready(self.next())
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Fails to serialize
+struct Unserializable;
+
+impl Serialize for Unserializable {
+    fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("not serializable"))
+    }
+}
+
+/// Deserialized from the reason of a rejected promise
+#[derive(Deserialize)]
+struct HttpError {
+    message: String,
+    status: u16,
+}
+
+/// Awaits `promise` on the executor, `Error` is not `Send` so only the
+/// rejection leaves it
+fn await_rejection<'a, T>(
+    cx: &mut FunctionContext<'a>,
+    promise: Handle<'a, JsPromise>,
+    settle: impl FnOnce(Result<T, Rejection>) + Send + 'static,
+) -> NeonResult<()>
+where
+    T: serde::de::DeserializeOwned + Send + 'static,
+{
+    let future = neon_serde::from_promise::<_, T>(cx, promise).or_else(|err| err.or_throw(cx))?;
+    neon_serde::executor::spawn(async move {
+        let result = match future.await {
+            Ok(value) => Ok(value),
+            Err(neon_serde::errors::Error::Rejected { reason }) => Err(reason),
+            Err(err) => Err(Rejection {
+                message: err.to_string(),
+                name: None,
+                code: None,
+                reason: neon_serde::Value::Null,
+            }),
+        };
+        settle(result);
+    });
+    Ok(())
+}
+
+/// Exported as a `const`, nested to check deep freezing
+#[derive(Serialize)]
+struct Config {
+    name: &'static str,
+    limits: Limits,
+    tags: &'static [&'static str],
+}
+
+#[derive(Serialize)]
+struct Limits {
+    connections: u32,
+    timeout: Option<u32>,
+}
+
+/// Exported as a class, `new Counter(1)`
+struct Counter {
+    count: u32,
+}
+
+/// Exported as a class to check receivers of another class
+struct Stack {
+    items: Vec<String>,
+}
+
+/// Ready after `millis`, woken from a timer thread
+struct Delay {
+    millis: u64,
+    done: Arc<Mutex<bool>>,
+    started: bool,
+}
+
+impl Delay {
+    fn new(millis: u64) -> Self {
+        Delay {
+            millis,
+            done: Arc::default(),
+            started: false,
+        }
+    }
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<()> {
+        if *self.done.lock().unwrap() {
+            return Poll::Ready(());
+        }
+        if !self.started {
+            self.started = true;
+            let (millis, done, waker) = (self.millis, Arc::clone(&self.done), cx.waker().clone());
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(millis));
+                *done.lock().unwrap() = true;
+                waker.wake();
+            });
+        }
+        Poll::Pending
+    }
+}
+
+export! {
+    #![register]
+
+    fn say_hello(name: String) -> String {
+        format!("Hello, {}!", name)
+    }
+
+    fn greet(user: User) -> String {
+        format!("{} is {} years old", user.name, user.age)
+    }
+
+    fn fibonacci(n: i32) -> i32 {
+        match n {
+            1 | 2 => 1,
+            n => fibonacci(n - 1) + fibonacci(n - 2),
+        }
+    }
+
+    fn sort_utf8_bytes(str: String) -> serde_bytes::ByteBuf {
+        let mut bytes = str.into_bytes();
+        bytes.sort();
+        serde_bytes::ByteBuf::from(bytes)
+    }
+
+    fn expect_buffer_only(_buff: serde_bytes::ByteBuf) -> () {}
+
+    fn expect_array(_buff: Vec<u8>) -> () {}
+
+    fn maybe_say_hello(user: Option<User>) -> Option<String> {
+        user.map(greet)
+    }
+
+    fn double_scores(scores: HashMap<u32, u32>) -> HashMap<u32, u32> {
+        scores.into_iter().map(|(id, score)| (id, score * 2)).collect()
+    }
+
+    fn grid() -> HashMap<(u32, u32), bool> {
+        HashMap::from([((0, 0), true)])
+    }
+
+    fn parse_form<'a>(
+        cx: &mut FunctionContext<'a>,
+        form: Handle<'a, JsValue>,
+    ) -> Result<Form, neon_serde::errors::Error> {
+        neon_serde::from_value_with(cx, form, Coercion::Lenient)
+    }
+
+    fn parse_strict_id<'a>(
+        cx: &mut FunctionContext<'a>,
+        id: Handle<'a, JsValue>,
+    ) -> Result<u32, neon_serde::errors::Error> {
+        neon_serde::from_value_with(cx, id, Coercion::Strict)
+    }
+
+    fn parse_age(age: String) -> Result<u16, std::num::ParseIntError> {
+        age.parse()
+    }
+
+    fn request(code: String, retry: bool) -> Result<String, AppError> {
+        if code.is_empty() {
+            Ok("done".into())
+        } else {
+            Err(AppError { code, retry })
+        }
+    }
+
+    fn check_status(status: u16) -> Result<(), Status> {
+        if status < 400 {
+            Ok(())
+        } else {
+            Err(Status(status))
+        }
+    }
+
+    fn to_char(text: String) -> Result<char, neon_serde::errors::Error> {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(neon_serde::errors::Error::StringTooLongForChar { len: text.len() }),
+        }
+    }
+
+    fn explode(reason: String) -> u32 {
+        panic!("{}", reason)
+    }
+
+    fn explode_static() {
+        panic!("static message")
+    }
+
+    async fn delayed(value: u32, millis: u64) -> u32 {
+        Delay::new(millis).await;
+        value
+    }
+
+    async fn delayed_parse(age: String) -> Result<u16, std::num::ParseIntError> {
+        Delay::new(1).await;
+        age.parse()
+    }
+
+    async fn delayed_explode() -> u32 {
+        Delay::new(1).await;
+        panic!("async boom")
+    }
+
+    #[blocking]
+    fn blocking_fibonacci(n: i32) -> i32 {
+        fibonacci(n)
+    }
+
+    #[blocking]
+    fn blocking_request(code: String) -> Result<(), AppError> {
+        Err(AppError { code, retry: false })
+    }
+
+    #[blocking]
+    fn blocking_explode() -> u32 {
+        panic!("blocking boom")
+    }
+
+    fn call_twice<'a>(
+        cx: &mut FunctionContext<'a>,
+        callback: Handle<'a, JsFunction>,
+    ) -> JsResult<'a, JsValue> {
+        callback.call_with(cx).apply::<JsValue, _>(cx)?;
+        callback.call_with(cx).apply(cx)
+    }
+
+    fn call_with_tuple<'a>(
+        cx: &mut FunctionContext<'a>,
+        function: Handle<'a, JsFunction>,
+    ) -> Result<String, neon_serde::errors::Error> {
+        let this = cx.undefined();
+        neon_serde::call_with(cx, function, this, &("Bob", 32, true))
+    }
+
+    fn call_with_struct<'a>(
+        cx: &mut FunctionContext<'a>,
+        function: Handle<'a, JsFunction>,
+    ) -> Result<u32, neon_serde::errors::Error> {
+        let this = cx.undefined();
+        let person = Person {
+            name: "Alice".into(),
+            age: 27,
+        };
+        neon_serde::call_with(cx, function, this, &person)
+    }
+
+    fn call_with_unit<'a>(
+        cx: &mut FunctionContext<'a>,
+        function: Handle<'a, JsFunction>,
+        this: Handle<'a, JsObject>,
+    ) -> Result<Option<String>, neon_serde::errors::Error> {
+        neon_serde::call_with(cx, function, this, &())
+    }
+
+    fn call_with_number<'a>(
+        cx: &mut FunctionContext<'a>,
+        function: Handle<'a, JsFunction>,
+    ) -> Result<(), neon_serde::errors::Error> {
+        let this = cx.undefined();
+        neon_serde::call_with(cx, function, this, &42)
+    }
+
+    fn identity<'a>(value: Handle<'a, JsValue>) -> JsResult<'a, JsValue> {
+        Ok(value)
+    }
+
+    fn own_keys<'a>(cx: &mut FunctionContext<'a>, object: Handle<'a, JsObject>) -> JsResult<'a, JsArray> {
+        object.get_own_property_names(cx)
+    }
+
+    fn labelled<'a>(cx: &mut FunctionContext<'a>, label: String, value: Handle<'a, JsNumber>) -> String {
+        format!("{}: {}", label, value.value(cx))
+    }
+
+    fn count_arguments<'a>(cx: &mut FunctionContext<'a>, label: String) -> String {
+        format!("{}: {}", label, cx.len())
+    }
+
+    #[options]
+    fn listen(host: String = "localhost".into(), port: u16 = 80) -> String {
+        format!("{}:{}", host, port)
+    }
+
+    #[options]
+    fn connect(host: String, port: u16 = 80, secure: Option<bool>) -> String {
+        let scheme = if secure.unwrap_or(false) { "https" } else { "http" };
+        format!("{}://{}:{}", scheme, host, port)
+    }
+
+    fn repeat(text: String, times: usize = 2) -> String {
+        text.repeat(times)
+    }
+
+    fn sum(#[rest] values: Vec<f64>) -> f64 {
+        values.iter().fold(0.0, |sum, value| sum + value)
+    }
+
+    fn join(separator: String, #[rest] parts: Vec<String>) -> String {
+        parts.join(&separator)
+    }
+
+    #[options]
+    fn tag(name: String, #[rest] tags: Vec<String>) -> String {
+        format!("{} [{}]", name, tags.join(", "))
+    }
+
+    impl Counter {
+        fn new(start: u32) -> Self {
+            Counter { count: start }
+        }
+
+        fn increment(&mut self) -> u32 {
+            self.count += 1;
+            self.count
+        }
+
+        fn add(&mut self, amount: u32) -> u32 {
+            self.count += amount;
+            self.count
+        }
+
+        fn get(&self) -> u32 {
+            self.count
+        }
+
+        fn describe(count: u32) -> String {
+            format!("counted to {}", count)
+        }
+    }
+
+    impl Stack {
+        fn new() -> Self {
+            Stack { items: Vec::new() }
+        }
+
+        fn push(&mut self, item: String) -> usize {
+            self.items.push(item);
+            self.items.len()
+        }
+
+        fn peek(&self) -> Option<String> {
+            self.items.last().cloned()
+        }
+    }
+
+    const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+    const DEFAULTS: Config = Config {
+        name: "defaults",
+        limits: Limits {
+            connections: 8,
+            timeout: None,
+        },
+        tags: &["mutable"],
+    };
+
+    #[frozen]
+    const CONFIG: Config = Config {
+        name: "app",
+        limits: Limits {
+            connections: 64,
+            timeout: Some(30),
+        },
+        tags: &["a", "b"],
+    };
+
+    #[frozen]
+    const PORTS: &[u16] = &[80, 443];
+
+    #[frozen]
+    const ANSWER: u32 = 42;
+
+    fn apply_twice<'a>(
+        cx: &mut FunctionContext<'a>,
+        value: i32,
+        f: JsCallback<(i32,), i32>,
+    ) -> Result<i32, neon_serde::errors::Error> {
+        let once = f.call(cx, &(value,))?;
+        f.call(cx, &(once,))
+    }
+
+    #[blocking]
+    fn process(
+        items: Vec<String>,
+        progress: JsCallback<(usize, usize), bool>,
+    ) -> Result<usize, String> {
+        for done in 0..items.len() {
+            let proceed = progress
+                .call_blocking((done, items.len()))
+                .map_err(|err| err.to_string())?;
+            if !proceed {
+                return Ok(done);
+            }
+        }
+        Ok(items.len())
+    }
+
+    fn notify_later(messages: Vec<String>, listener: JsCallback<(String, usize)>) {
+        thread::spawn(move || {
+            for (i, message) in messages.into_iter().enumerate() {
+                listener.send((message, i)).unwrap();
+            }
+        });
+    }
+
+    #[blocking]
+    fn emit_counts(count: u32, capacity: usize, events: Emitter) -> Result<u32, String> {
+        let events = events.with_capacity(capacity);
+        for n in 0..count {
+            events.emit("count", &n).map_err(|err| err.to_string())?;
+        }
+        events.flush().map_err(|err| err.to_string())?;
+        Ok(count)
+    }
+
+    fn try_emit_twice(events: Emitter) -> Result<(), neon_serde::errors::Error> {
+        let events = events.with_capacity(1);
+        events.try_emit("first", &1)?;
+        events.try_emit("second", &2)
+    }
+
+    fn rethrow_rejection<'a>(
+        cx: &mut FunctionContext<'a>,
+        promise: Handle<'a, JsPromise>,
+    ) -> JsResult<'a, JsPromise> {
+        let channel = cx.channel();
+        let (deferred, settled) = cx.promise();
+        await_rejection(cx, promise, move |result: Result<f64, Rejection>| {
+            deferred.settle_with(&channel, move |mut cx| match result {
+                Ok(value) => Ok(cx.number(value)),
+                Err(reason) => neon_serde::errors::Error::Rejected { reason }.or_throw(&mut cx),
+            });
+        })?;
+        Ok(settled)
+    }
+
+    fn rejection_status<'a>(
+        cx: &mut FunctionContext<'a>,
+        promise: Handle<'a, JsPromise>,
+    ) -> JsResult<'a, JsPromise> {
+        let channel = cx.channel();
+        let (deferred, settled) = cx.promise();
+        await_rejection(cx, promise, move |result: Result<(), Rejection>| {
+            let status = match result {
+                Ok(()) => Err("fulfilled".to_string()),
+                Err(reason) => reason
+                    .deserialize_reason::<HttpError>()
+                    .map(|err| format!("{} {}", err.status, err.message))
+                    .map_err(|err| err.to_string()),
+            };
+            neon_serde::settle(&channel, deferred, status);
+        })?;
+        Ok(settled)
+    }
+
+    fn copy_value<'a>(
+        cx: &mut FunctionContext<'a>,
+        value: Handle<'a, JsValue>,
+    ) -> JsResult<'a, JsValue> {
+        neon_serde::Value::from_js(cx, value)
+            .and_then(|value| value.to_js(cx))
+            .or_else(|err| err.or_throw(cx))
+    }
+
+    fn count_to<'a>(cx: &mut FunctionContext<'a>, n: u32) -> JsResult<'a, JsObject> {
+        neon_serde::to_iterator(cx, 1..=n)
+    }
+
+    fn count_forever<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsObject> {
+        DROPPED.store(false, Ordering::SeqCst);
+        neon_serde::to_iterator(cx, Tracked(0))
+    }
+
+    fn unserializable_items<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsObject> {
+        neon_serde::to_iterator(cx, [Unserializable])
+    }
+
+    fn ticks<'a>(cx: &mut FunctionContext<'a>, len: u32) -> JsResult<'a, JsObject> {
+        neon_serde::to_async_iterator(
+            cx,
+            Ticks {
+                next: 0,
+                len,
+                delay: None,
+            },
+        )
+    }
+
+    fn ticks_forever<'a>(cx: &mut FunctionContext<'a>) -> JsResult<'a, JsObject> {
+        DROPPED.store(false, Ordering::SeqCst);
+        neon_serde::to_async_iterator(cx, Tracked(0))
+    }
+
+    fn is_dropped() -> bool {
+        DROPPED.load(Ordering::SeqCst)
+    }
+
+    #[js_name = "sayGoodbye"]
+    fn say_goodbye(name: String) -> String {
+        format!("Goodbye, {}!", name)
+    }
+
+    #[cfg(any())]
+    fn never_exported() -> u32 {
+        0
+    }
+
+    mod text {
+        fn shout(text: String) -> String {
+            text.to_uppercase()
+        }
+
+        #[cfg(unix)]
+        fn line_ending() -> String {
+            "\n".into()
+        }
+
+        #[cfg(not(unix))]
+        fn line_ending() -> String {
+            "\r\n".into()
+        }
+
+        #[js_name = "isEmpty"]
+        fn is_empty(text: String) -> bool {
+            text.is_empty()
+        }
+
+        #[cfg(any())]
+        fn never_exported() -> u32 {
+            0
+        }
+    }
+
+    #[js_name = "nested"]
+    mod nested_namespace {
+        mod inner {
+            fn depth() -> u32 {
+                2
+            }
+        }
+    }
+}
+
+mod math {
+    use neon_serde::export;
+
+    export! {
+        #![register]
+
+        fn add(a: f64, b: f64) -> f64 {
+            a + b
+        }
+    }
+}
+
+fn version(mut cx: FunctionContext) -> JsResult<JsString> {
+    Ok(cx.string(env!("CARGO_PKG_VERSION")))
+}
+
+#[neon::main]
+fn main(mut cx: ModuleContext) -> NeonResult<()> {
+    register(&mut cx)?;
+    math::register(&mut cx)?;
+    cx.export_function("version", version)?;
+    Ok(())
+}